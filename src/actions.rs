@@ -1,13 +1,15 @@
 use crate::game::PlayerReference;
 use crate::GameCard;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BreatheOrTravel {
     Breathe,
     Travel,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
     OxygenSiphon { target: PlayerReference },
     HackSuit { target: PlayerReference },
@@ -18,7 +20,32 @@ pub enum Action {
     Tether { target: PlayerReference },
 }
 
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.attacking() {
+            None => write!(f, "{:?}", self.card()),
+            Some(target) => write!(f, "{:?} on player {}", self.card(), target.0),
+        }
+    }
+}
+
 impl Action {
+    /// The action for playing the card at the target, if it can be played as an action.
+    ///
+    /// The target is ignored for cards that don't attack anyone.
+    pub fn from_card(card: GameCard, target: PlayerReference) -> Option<Action> {
+        match card {
+            GameCard::OxygenSiphon => Some(Action::OxygenSiphon { target }),
+            GameCard::HackSuit => Some(Action::HackSuit { target }),
+            GameCard::TractorBeam => Some(Action::TractorBeam { target }),
+            GameCard::RocketBooster => Some(Action::RocketBooster),
+            GameCard::LaserBlast => Some(Action::LaserBlast { target }),
+            GameCard::HoleInSuit => Some(Action::HoleInSuit { target }),
+            GameCard::Tether => Some(Action::Tether { target }),
+            GameCard::O1 | GameCard::O2 | GameCard::Shield => None,
+        }
+    }
+
     pub fn card(&self) -> GameCard {
         match self {
             Action::OxygenSiphon { .. } => GameCard::OxygenSiphon,
@@ -43,30 +70,35 @@ impl Action {
         }
     }
 
+    /// What the action needs from its target.
     pub fn rules(&self) -> ActionRules {
         match self {
-            Action::OxygenSiphon { .. } => ActionRules {
-                steal: Some(Steal {
-                    count: 2,
-                    visibility: StealAccess::Specific(GameCard::O1),
-                }),
-            },
+            // The target pays the siphon in oxygen like any other cost, and dies if they can't.
+            Action::OxygenSiphon { .. } => ActionRules::default(),
             Action::HackSuit { .. } => ActionRules {
                 steal: Some(Steal {
                     count: 1,
                     visibility: StealAccess::SeeCardsAndChoose,
                 }),
+                ..Default::default()
             },
             Action::TractorBeam { .. } => ActionRules {
                 steal: Some(Steal {
                     count: 1,
                     visibility: StealAccess::Random,
                 }),
+                ..Default::default()
             },
             Action::RocketBooster => ActionRules::default(),
-            Action::LaserBlast { .. } => ActionRules::default(),
+            Action::LaserBlast { .. } => ActionRules {
+                needs_space: true,
+                ..Default::default()
+            },
             Action::HoleInSuit { .. } => ActionRules::default(),
-            Action::Tether { .. } => ActionRules::default(),
+            Action::Tether { .. } => ActionRules {
+                needs_space: true,
+                ..Default::default()
+            },
         }
     }
 }
@@ -74,6 +106,9 @@ impl Action {
 #[derive(Default)]
 pub struct ActionRules {
     pub steal: Option<Steal>,
+
+    /// The target must have at least one space card, e.g. to be pushed back.
+    pub needs_space: bool,
 }
 
 pub struct Steal {
@@ -82,7 +117,6 @@ pub struct Steal {
 }
 
 pub enum StealAccess {
-    Specific(GameCard),
     Random,
     SeeCardsAndChoose,
 }
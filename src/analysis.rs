@@ -0,0 +1,146 @@
+use crate::events::GameEvent;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Statistics gathered from the events of any number of games.
+///
+/// Counts are keyed by name, e.g. `"LaserBlast"`, so they read well as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Analysis {
+    pub games: usize,
+    /// Turns taken by every player, across all games.
+    pub turns: usize,
+    pub shortest_game: Option<usize>,
+    pub longest_game: Option<usize>,
+    pub wins_by_seat: Vec<usize>,
+    pub win_reasons: BTreeMap<String, usize>,
+    pub deaths: BTreeMap<String, usize>,
+    pub actions_played: BTreeMap<String, usize>,
+    pub decisions_rejected: usize,
+    pub decisions_imposed: usize,
+    pub shields_used: usize,
+    pub space_cards_revealed: BTreeMap<String, usize>,
+}
+
+impl Analysis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one game, given every event it emitted.
+    pub fn add_game(&mut self, events: &[GameEvent]) {
+        self.games += 1;
+        let mut turns = 0;
+        for event in events {
+            match event {
+                GameEvent::GameStarted { player_count, .. } => self.add_seats(*player_count),
+                GameEvent::TurnStarted { .. } => turns += 1,
+                GameEvent::ActionPlayed { action, .. } => {
+                    count(&mut self.actions_played, format!("{:?}", action.card()));
+                }
+                GameEvent::DecisionRejected { .. } => self.decisions_rejected += 1,
+                GameEvent::DecisionImposed { .. } => self.decisions_imposed += 1,
+                GameEvent::ShieldUsed { .. } => self.shields_used += 1,
+                GameEvent::SpaceCardRevealed { card, .. } => {
+                    count(&mut self.space_cards_revealed, format!("{:?}", card));
+                }
+                GameEvent::PlayerDied { cause, .. } => {
+                    count(&mut self.deaths, format!("{:?}", cause));
+                }
+                GameEvent::GameWon { player, reason } => {
+                    self.add_seats(player.0 + 1);
+                    self.wins_by_seat[player.0] += 1;
+                    count(&mut self.win_reasons, format!("{:?}", reason));
+                }
+                _ => {}
+            }
+        }
+
+        self.turns += turns;
+        self.shortest_game = Some(self.shortest_game.map_or(turns, |t| t.min(turns)));
+        self.longest_game = Some(self.longest_game.map_or(turns, |t| t.max(turns)));
+    }
+
+    /// Games can have different numbers of players, so make room for the biggest.
+    fn add_seats(&mut self, player_count: usize) {
+        if self.wins_by_seat.len() < player_count {
+            self.wins_by_seat.resize(player_count, 0);
+        }
+    }
+
+    pub fn mean_turns(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.turns as f64 / self.games as f64
+    }
+}
+
+fn count(counts: &mut BTreeMap<String, usize>, key: String) {
+    *counts.entry(key).or_default() += 1;
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Games: {}", self.games)?;
+        writeln!(
+            f,
+            "Turns per game: {:.1} on average, {} to {}",
+            self.mean_turns(),
+            self.shortest_game.unwrap_or_default(),
+            self.longest_game.unwrap_or_default()
+        )?;
+        writeln!(f, "Wins by seat: {:?}", self.wins_by_seat)?;
+        writeln!(f, "Decisions rejected: {}", self.decisions_rejected)?;
+        writeln!(f, "Decisions imposed: {}", self.decisions_imposed)?;
+        writeln!(f, "Shields used: {}", self.shields_used)?;
+
+        let sections = [
+            ("Win reasons", &self.win_reasons),
+            ("Deaths", &self.deaths),
+            ("Actions played", &self.actions_played),
+            ("Space cards revealed", &self.space_cards_revealed),
+        ];
+        for (title, counts) in sections {
+            writeln!(f)?;
+            writeln!(f, "{}:", title)?;
+            for (name, n) in counts {
+                writeln!(f, "  {:<24} {:>8}", name, n)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Recorder;
+    use crate::{Game, PlayerController, RandomPlayerController};
+
+    #[test]
+    fn test_analysis() {
+        let mut analysis = Analysis::new();
+        for seed in 0..10 {
+            let recorder = Recorder::new();
+            let controllers: Vec<Box<dyn PlayerController>> = (0..3)
+                .map(|_| {
+                    Box::new(RandomPlayerController::with_seed(seed)) as Box<dyn PlayerController>
+                })
+                .collect();
+            let mut game = Game::new(Some(seed), controllers);
+            recorder.attach(&mut game);
+            game.simulate().unwrap();
+            analysis.add_game(&recorder.finish(&game).events);
+        }
+
+        assert_eq!(analysis.games, 10);
+        assert_eq!(analysis.wins_by_seat.len(), 3);
+        assert_eq!(analysis.wins_by_seat.iter().sum::<usize>(), 10);
+        assert_eq!(analysis.win_reasons.values().sum::<usize>(), 10);
+        assert!(analysis.shortest_game <= analysis.longest_game);
+        assert!(analysis.mean_turns() >= analysis.shortest_game.unwrap() as f64);
+        assert!(analysis.to_string().contains("Games: 10"));
+    }
+}
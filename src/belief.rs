@@ -0,0 +1,223 @@
+use crate::{Action, GameCard, GameEvent, PlayerReference};
+
+/// What one player has learned about the cards in everybody else's hands, from the events they
+/// saw.
+///
+/// Cards only ever leave a hand in ways everybody sees, except when they are stolen in secret. A
+/// player who isn't part of a secret steal can't know which card went, so they only stay sure of
+/// cards the victim held more than one of.
+///
+/// Only sound as long as every event the player saw is observed, in order, and nothing else is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeliefState {
+    viewer: PlayerReference,
+    known: Vec<Vec<GameCard>>,
+}
+
+impl BeliefState {
+    /// Knowing nothing yet.
+    pub fn new(viewer: PlayerReference, player_count: usize) -> Self {
+        Self {
+            viewer,
+            known: vec![Vec::new(); player_count],
+        }
+    }
+
+    /// Everything the viewer learned from their history, e.g. `VisibleState::history`.
+    pub fn from_history(
+        viewer: PlayerReference,
+        player_count: usize,
+        history: &[GameEvent],
+    ) -> Self {
+        let mut belief = Self::new(viewer, player_count);
+        for event in history {
+            belief.observe(event);
+        }
+        belief
+    }
+
+    pub fn viewer(&self) -> PlayerReference {
+        self.viewer
+    }
+
+    /// Cards the viewer is sure are in the player's hand. Always empty for the viewer, who can
+    /// just look.
+    pub fn known_cards(&self, player: &PlayerReference) -> &[GameCard] {
+        self.known.get(player.0).map_or(&[], |hand| hand.as_slice())
+    }
+
+    /// Learn from an event the viewer saw.
+    pub fn observe(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::GameStarted { dealt, .. } => {
+                for (idx, hand) in self.known.iter_mut().enumerate() {
+                    if idx != self.viewer.0 {
+                        *hand = dealt.clone();
+                    }
+                }
+            }
+            GameEvent::ActionPlayed { player, action } => {
+                self.forget(player, &action.card());
+                let secret_steal =
+                    matches!(action, Action::TractorBeam { .. } | Action::HackSuit { .. });
+                if let Some(target) = action.attacking() {
+                    if secret_steal && self.viewer != target && self.viewer != *player {
+                        self.forget_one_of_each(&target);
+                    }
+                }
+            }
+            GameEvent::ShieldUsed { player, .. } => self.forget(player, &GameCard::Shield),
+            GameEvent::CardsShown { from, cards, .. } => {
+                if let Some(hand) = self.hand_mut(from) {
+                    for card in cards {
+                        if !hand.contains(card) {
+                            hand.push(*card);
+                        }
+                    }
+                }
+            }
+            GameEvent::ChangeGiven { player, cards } => {
+                if let Some(hand) = self.hand_mut(player) {
+                    hand.extend(cards);
+                }
+            }
+            GameEvent::CardStolen { from, to, card } => {
+                self.forget(from, card);
+                if let Some(hand) = self.hand_mut(to) {
+                    hand.push(*card);
+                }
+            }
+            GameEvent::CardsDiscarded { player, cards } => {
+                for card in cards {
+                    self.forget(player, card);
+                }
+            }
+            GameEvent::Breathed { player } => self.forget(player, &GameCard::O1),
+            GameEvent::Travelled { player } => self.forget(player, &GameCard::O2),
+            GameEvent::PlayerDied { player, .. } => {
+                if let Some(hand) = self.hand_mut(player) {
+                    hand.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Somebody else's hand. None for the viewer, whose hand isn't tracked.
+    fn hand_mut(&mut self, player: &PlayerReference) -> Option<&mut Vec<GameCard>> {
+        if *player == self.viewer {
+            return None;
+        }
+        self.known.get_mut(player.0)
+    }
+
+    fn forget(&mut self, player: &PlayerReference, card: &GameCard) {
+        if let Some(hand) = self.hand_mut(player) {
+            if let Some(idx) = hand.iter().position(|c| c == card) {
+                hand.remove(idx);
+            }
+        }
+    }
+
+    /// One card went, but the viewer doesn't know which.
+    fn forget_one_of_each(&mut self, player: &PlayerReference) {
+        if let Some(hand) = self.hand_mut(player) {
+            let mut kinds = hand.clone();
+            kinds.sort();
+            kinds.dedup();
+            for card in kinds {
+                if let Some(idx) = hand.iter().position(|c| *c == card) {
+                    hand.remove(idx);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Decision, Game, GameObserver, PlayerController, RandomPlayerController, Scenario,
+        SeatedObserver,
+    };
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_hack_suit_shows_the_attacker_the_hand() {
+        let mut state = Scenario::new(3)
+            .hand(
+                1,
+                [GameCard::LaserBlast, GameCard::LaserBlast, GameCard::O1],
+            )
+            .deck([GameCard::HackSuit])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        state
+            .apply(Decision::PlayAction(Some(Action::HackSuit {
+                target: PlayerReference(1),
+            })))
+            .unwrap();
+        let known = |state: &crate::GameState, viewer, player| {
+            state
+                .belief(&PlayerReference(viewer))
+                .unwrap()
+                .known_cards(&PlayerReference(player))
+                .to_vec()
+        };
+        assert_eq!(
+            known(&state, 0, 1),
+            vec![GameCard::O1, GameCard::LaserBlast]
+        );
+        assert_eq!(known(&state, 2, 1), vec![]);
+
+        state
+            .apply(Decision::TakeCard(GameCard::LaserBlast))
+            .unwrap();
+        // The attacker only saw one of each kind, so can't tell whether a laser blast is left.
+        assert_eq!(known(&state, 0, 1), vec![GameCard::O1]);
+        assert_eq!(known(&state, 1, 0), vec![GameCard::LaserBlast]);
+        assert_eq!(known(&state, 2, 0), vec![]);
+    }
+
+    #[test]
+    fn test_seated_observers_see_their_history() {
+        for seed in 0..10 {
+            let controllers = (0..4)
+                .map(|seat| {
+                    Box::new(RandomPlayerController::with_seed(seed * 10 + seat))
+                        as Box<dyn PlayerController>
+                })
+                .collect();
+            let mut game = Game::new(Some(seed), controllers);
+            let mut logs = Vec::new();
+            for seat in (0..4).map(PlayerReference) {
+                let log = Rc::new(RefCell::new(Vec::new()));
+                let recorded = log.clone();
+                let record = move |_: &Game, event: &GameEvent| {
+                    recorded.borrow_mut().push(event.clone());
+                };
+                let observer: Box<dyn GameObserver> = Box::new(SeatedObserver::new(seat, record));
+                game.add_observer(observer);
+                logs.push(log);
+            }
+            game.simulate().unwrap();
+
+            for (idx, log) in logs.iter().enumerate() {
+                let seat = PlayerReference(idx);
+                let log = log.borrow();
+                assert_eq!(*log, game.state().history(&seat));
+                assert!(!log.iter().any(|event| matches!(
+                    event,
+                    GameEvent::CardDrawn { player, .. } if *player != seat
+                )));
+                assert_eq!(
+                    &BeliefState::from_history(seat, 4, &log),
+                    game.state().belief(&seat).unwrap()
+                );
+            }
+        }
+    }
+}
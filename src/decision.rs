@@ -0,0 +1,154 @@
+use crate::actions::BreatheOrTravel;
+use crate::{Action, DeathCause, GameCard, PlayerReference};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A question the engine is waiting for a player to answer before the game can continue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingDecision {
+    /// Play an action card, or nothing to move on to breathing or travelling.
+    PlayAction { player: PlayerReference },
+
+    /// Use a shield against the action or not.
+    Defend {
+        player: PlayerReference,
+        action: Action,
+    },
+
+    /// Pick which of the target's cards a hack suit takes.
+    ChooseCardToTake {
+        player: PlayerReference,
+        target: PlayerReference,
+        options: Vec<GameCard>,
+    },
+
+    /// Only asked when the player has both an O1 and an O2.
+    BreatheOrTravel { player: PlayerReference },
+
+    /// A meteoroid hit a player holding too many cards.
+    ForcedDiscard {
+        player: PlayerReference,
+        card_count: usize,
+    },
+
+    /// A wormhole makes the player swap spaces with somebody.
+    ChooseSwapTarget { player: PlayerReference },
+
+    /// Only asked when there is more than one way to pay, e.g. with an O2 or two O1s. Paying
+    /// too much is given back in O1s, as far as `RuleSet::oxygen_change` allows.
+    PayOxygen {
+        player: PlayerReference,
+        cost: usize,
+        /// What the player dies of if they can't pay.
+        cause: DeathCause,
+        /// Every way to pay, fewest O2s first.
+        options: Vec<Vec<GameCard>>,
+    },
+}
+
+impl PendingDecision {
+    /// Who has to answer.
+    pub fn player(&self) -> PlayerReference {
+        match self {
+            PendingDecision::PlayAction { player }
+            | PendingDecision::Defend { player, .. }
+            | PendingDecision::ChooseCardToTake { player, .. }
+            | PendingDecision::BreatheOrTravel { player }
+            | PendingDecision::ForcedDiscard { player, .. }
+            | PendingDecision::ChooseSwapTarget { player }
+            | PendingDecision::PayOxygen { player, .. } => *player,
+        }
+    }
+
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            PendingDecision::PlayAction { .. } => "play_action",
+            PendingDecision::Defend { .. } => "defend",
+            PendingDecision::ChooseCardToTake { .. } => "choose_card_to_take",
+            PendingDecision::BreatheOrTravel { .. } => "breathe_or_travel",
+            PendingDecision::ForcedDiscard { .. } => "forced_discard",
+            PendingDecision::ChooseSwapTarget { .. } => "choose_player_to_swap_with",
+            PendingDecision::PayOxygen { .. } => "pay_oxygen",
+        }
+    }
+}
+
+/// An answer to a `PendingDecision`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Decision {
+    PlayAction(Option<Action>),
+    BreatheOrTravel(BreatheOrTravel),
+    Defend(bool),
+    ForcedDiscard(Vec<GameCard>),
+    SwapWith(PlayerReference),
+    TakeCard(GameCard),
+    PayOxygen(Vec<GameCard>),
+}
+
+impl Decision {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Decision::PlayAction(_) => "play_action",
+            Decision::BreatheOrTravel(_) => "breathe_or_travel",
+            Decision::Defend(_) => "defend",
+            Decision::ForcedDiscard(_) => "forced_discard",
+            Decision::SwapWith(_) => "choose_player_to_swap_with",
+            Decision::TakeCard(_) => "choose_card_to_take",
+            Decision::PayOxygen(_) => "pay_oxygen",
+        }
+    }
+}
+
+impl Display for PendingDecision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PendingDecision::PlayAction { player } => {
+                write!(f, "Player {} may play an action card.", player.0)
+            }
+            PendingDecision::Defend { player, action } => write!(
+                f,
+                "Player {} may shield against {:?}.",
+                player.0,
+                action.card()
+            ),
+            PendingDecision::ChooseCardToTake { player, target, .. } => write!(
+                f,
+                "Player {} picks a card to take from player {}.",
+                player.0, target.0
+            ),
+            PendingDecision::BreatheOrTravel { player } => {
+                write!(f, "Player {} must breathe or travel.", player.0)
+            }
+            PendingDecision::ForcedDiscard { player, card_count } => {
+                write!(f, "Player {} must discard {} cards.", player.0, card_count)
+            }
+            PendingDecision::ChooseSwapTarget { player } => {
+                write!(f, "Player {} picks who to swap spaces with.", player.0)
+            }
+            PendingDecision::PayOxygen { player, cost, .. } => {
+                write!(f, "Player {} picks how to pay {} oxygen.", player.0, cost)
+            }
+        }
+    }
+}
+
+impl Display for Decision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Decision::PlayAction(None) => write!(f, "Nothing, move on to breathing or travelling"),
+            Decision::PlayAction(Some(action)) => write!(f, "{}", action),
+            Decision::BreatheOrTravel(BreatheOrTravel::Breathe) => {
+                write!(f, "Breathe, using an O1")
+            }
+            Decision::BreatheOrTravel(BreatheOrTravel::Travel) => {
+                write!(f, "Travel, using an O2")
+            }
+            Decision::Defend(true) => write!(f, "Use a shield"),
+            Decision::Defend(false) => write!(f, "Take the hit"),
+            Decision::ForcedDiscard(cards) => write!(f, "Discard {:?}", cards),
+            Decision::SwapWith(player) => write!(f, "Swap spaces with player {}", player.0),
+            Decision::TakeCard(card) => write!(f, "Take {:?}", card),
+            Decision::PayOxygen(cards) => write!(f, "Pay with {:?}", cards),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::errors::{parse_toml, TomlError};
+use crate::{GameCard, SpaceCard};
+use miette::{Diagnostic, IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// The decks of the official base game, bundled into the crate.
+pub const BASE_DECKS: &str = include_str!("../decks/base.toml");
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum DeckError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] TomlError),
+
+    #[error("The space deck has no cards.")]
+    EmptySpaceDeck,
+
+    #[error("Dealing to {player_count} players needs {needed} {card:?} cards but the deck only has {available}.")]
+    NotEnoughToDeal {
+        player_count: usize,
+        card: GameCard,
+        needed: usize,
+        available: usize,
+    },
+
+    #[error("There would be nothing left to draw after dealing to {player_count} players.")]
+    #[diagnostic(help("Add more cards to the game deck."))]
+    NothingToDraw { player_count: usize },
+}
+
+/// How many of each card are in the game and space decks.
+///
+/// Decks are written in TOML, with a count for every card by name. See [`BASE_DECKS`] for the
+/// official game, which is also what `DeckConfig::default` gives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeckConfig {
+    pub game_cards: BTreeMap<GameCard, usize>,
+    pub space_cards: BTreeMap<SpaceCard, usize>,
+}
+
+impl Default for DeckConfig {
+    fn default() -> Self {
+        Self::base()
+    }
+}
+
+impl DeckConfig {
+    /// The official base game.
+    pub fn base() -> Self {
+        static BASE: OnceLock<DeckConfig> = OnceLock::new();
+        BASE.get_or_init(|| {
+            Self::from_toml("base.toml", BASE_DECKS).expect("the bundled decks are valid")
+        })
+        .clone()
+    }
+
+    /// Parse and validate decks, naming the source in any error.
+    pub fn from_toml(name: &str, text: &str) -> Result<Self, DeckError> {
+        let decks: DeckConfig = parse_toml(name, text)?;
+        decks.validate()?;
+        Ok(decks)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> miette::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Reading {}", path.display()))?;
+        Ok(Self::from_toml(&path.display().to_string(), &text)?)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("decks are always representable in TOML")
+    }
+
+    /// Checks that don't depend on the number of players.
+    pub fn validate(&self) -> Result<(), DeckError> {
+        if self.space_cards.values().sum::<usize>() == 0 {
+            return Err(DeckError::EmptySpaceDeck);
+        }
+        Ok(())
+    }
+
+    /// Whether every player can be dealt the starting hand, with cards left over to draw.
+    pub fn check_deal(
+        &self,
+        player_count: usize,
+        starting_hand: &[(GameCard, usize)],
+    ) -> Result<(), DeckError> {
+        self.validate()?;
+        for (card, per_player) in starting_hand {
+            let available = self.game_card_count(*card);
+            let needed = per_player * player_count;
+            if available < needed {
+                return Err(DeckError::NotEnoughToDeal {
+                    player_count,
+                    card: *card,
+                    needed,
+                    available,
+                });
+            }
+        }
+        let dealt: usize = starting_hand.iter().map(|(_, count)| count).sum();
+        if self.game_cards.values().sum::<usize>() <= dealt * player_count {
+            return Err(DeckError::NothingToDraw { player_count });
+        }
+        Ok(())
+    }
+
+    pub fn game_card_count(&self, card: GameCard) -> usize {
+        self.game_cards.get(&card).copied().unwrap_or_default()
+    }
+
+    /// Every game card, in a fixed order so a seed always shuffles them the same way.
+    pub fn game_deck(&self) -> Vec<GameCard> {
+        self.game_cards
+            .iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(*card, *count))
+            .collect()
+    }
+
+    /// Every space card, in a fixed order so a seed always shuffles them the same way.
+    pub fn space_deck(&self) -> Vec<SpaceCard> {
+        self.space_cards
+            .iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(card.clone(), *count))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAND: [(GameCard, usize); 2] = [(GameCard::O2, 1), (GameCard::O1, 4)];
+
+    #[test]
+    fn test_base_decks() {
+        let decks = DeckConfig::base();
+        assert_eq!(decks.game_deck().len(), 78);
+        assert_eq!(decks.game_card_count(GameCard::O2), 10);
+        assert_eq!(decks.space_deck().len(), 42);
+        for player_count in 2..=6 {
+            decks.check_deal(player_count, &HAND).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let decks = DeckConfig::base();
+        assert_eq!(
+            DeckConfig::from_toml("copy", &decks.to_toml()).unwrap(),
+            decks
+        );
+    }
+
+    #[test]
+    fn test_unknown_card() {
+        let text = BASE_DECKS.replace("Tether = 4", "Teleporter = 4");
+        let err = DeckConfig::from_toml("custom.toml", &text).unwrap_err();
+        let DeckError::Parse(err) = err else {
+            panic!("expected a parse error, got {:?}", err);
+        };
+        let span = err.span.unwrap();
+        assert_eq!(
+            &text[span.offset()..span.offset() + span.len()],
+            "Teleporter"
+        );
+    }
+
+    #[test]
+    fn test_not_enough_oxygen() {
+        let text = BASE_DECKS.replace("O1 = 38", "O1 = 10");
+        let decks = DeckConfig::from_toml("custom.toml", &text).unwrap();
+        assert!(decks.check_deal(2, &HAND).is_ok());
+        assert!(matches!(
+            decks.check_deal(3, &HAND),
+            Err(DeckError::NotEnoughToDeal {
+                card: GameCard::O1,
+                needed: 12,
+                available: 10,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_empty_space_deck() {
+        let text = "[game_cards]\nO1 = 40\nO2 = 10\n\n[space_cards]\n";
+        assert!(matches!(
+            DeckConfig::from_toml("custom.toml", text),
+            Err(DeckError::EmptySpaceDeck)
+        ));
+    }
+}
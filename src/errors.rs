@@ -1,5 +1,6 @@
 use crate::{GameCard, PlayerReference};
-use miette::Diagnostic;
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use serde::de::DeserializeOwned;
 use thiserror::Error;
 
 /// An error specific to a player attempting an action incorrectly.
@@ -14,12 +15,67 @@ pub enum SelfishError {
     #[error("Player {0:?} does not have enough cards to steal {1}!")]
     PlayerDoesNotHaveEnoughCards(PlayerReference, usize),
 
+    #[error("Player {0:?} has not travelled into space yet!")]
+    PlayerHasNoSpace(PlayerReference),
+
     #[error("You don't have a {0:?} card!")]
     PlayerDoesNotHaveThisCard(GameCard),
 
     #[error("Player {0:?} does not exist!")]
     PlayerDoesNotExist(PlayerReference),
 
+    #[error("Player {0:?} is dead!")]
+    PlayerIsDead(PlayerReference),
+
+    #[error("You can't use action cards in a solar flare!")]
+    InSolarFlare,
+
+    #[error("Player {0:?} does not have a {1:?} card!")]
+    TargetDoesNotHaveThisCard(PlayerReference, GameCard),
+
+    #[error("You can't swap spaces with player {0:?}!")]
+    InvalidSwapTarget(PlayerReference),
+
+    #[error("Player {0:?} has no controller to decide for them.")]
+    NoController(PlayerReference),
+
+    #[error("The game isn't waiting for a decision.")]
+    NoDecisionPending,
+
+    #[error("Expected a {expected} decision but got {actual}.")]
+    WrongDecision {
+        expected: &'static str,
+        actual: &'static str,
+    },
+
     #[error("Invalid discard count. Expected {expected} but got {actual}.")]
     InvalidDiscardCount { expected: usize, actual: usize },
+
+    #[error("A player can only forfeit between actions.")]
+    CantForfeitNow,
+
+    #[error("{cards:?} isn't a way to pay {cost} oxygen.")]
+    InvalidPayment { cost: usize, cards: Vec<GameCard> },
+}
+
+/// A config file that isn't TOML, or doesn't describe what it should.
+#[derive(Error, Debug, Diagnostic)]
+#[error("Couldn't read {name}: {message}")]
+pub struct TomlError {
+    pub name: String,
+    pub message: String,
+    #[source_code]
+    source_code: NamedSource,
+    #[label("here")]
+    pub span: Option<SourceSpan>,
+}
+
+/// Parse TOML, pointing at the problem in the text if there is one.
+pub(crate) fn parse_toml<T: DeserializeOwned>(name: &str, text: &str) -> Result<T, TomlError> {
+    toml::from_str(text).map_err(|err| TomlError {
+        name: name.to_string(),
+        message: err.message().to_string(),
+        source_code: NamedSource::new(name, text.to_string()),
+        span: err.span().map(SourceSpan::from),
+    })
 }
@@ -0,0 +1,328 @@
+use crate::{Action, Decision, Game, GameCard, PlayerReference, SpaceCard};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Something that happened during a game, emitted in the order it happened.
+///
+/// Most events happen in front of everybody, but some are only seen by the players involved, e.g.
+/// the card somebody drew. Each one is emitted with its `Audience`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    GameStarted {
+        player_count: usize,
+        ship_distance: usize,
+        /// The hand every player was dealt, which everybody knows. Empty when the game started
+        /// part way through, e.g. from a `Scenario`.
+        #[serde(default)]
+        dealt: Vec<GameCard>,
+    },
+    TurnStarted {
+        player: PlayerReference,
+    },
+    /// From the pickup phase, or from a space card.
+    CardDrawn {
+        player: PlayerReference,
+        card: GameCard,
+    },
+    ActionPlayed {
+        player: PlayerReference,
+        action: Action,
+    },
+    /// The player's controller answered with something the rules don't allow, which had no
+    /// effect. See `MisconductPolicy` for what happens next. Only the player, and the target of
+    /// their hack suit, hear about it.
+    DecisionRejected {
+        player: PlayerReference,
+        decision: Decision,
+        reason: String,
+    },
+    /// The controller didn't give a valid answer in time, so the game answered for them. Heard by
+    /// the same players as `DecisionRejected`.
+    DecisionImposed {
+        player: PlayerReference,
+        decision: Decision,
+    },
+    /// The player lost their action phase as a penalty for misconduct.
+    ActionsSkipped {
+        player: PlayerReference,
+    },
+    ShieldUsed {
+        player: PlayerReference,
+        action: Action,
+    },
+    /// The hack suit showed `to` one of each kind of card in `from`'s hand, to take one.
+    CardsShown {
+        from: PlayerReference,
+        to: PlayerReference,
+        cards: Vec<GameCard>,
+    },
+    CardStolen {
+        from: PlayerReference,
+        to: PlayerReference,
+        card: GameCard,
+    },
+    CardsDiscarded {
+        player: PlayerReference,
+        cards: Vec<GameCard>,
+    },
+    /// O1s given back to a player who paid more oxygen than they owed, or to a player owed the
+    /// rest of a siphon that was paid with an O2.
+    ChangeGiven {
+        player: PlayerReference,
+        cards: Vec<GameCard>,
+    },
+    Breathed {
+        player: PlayerReference,
+    },
+    Travelled {
+        player: PlayerReference,
+    },
+    SpaceCardRevealed {
+        player: PlayerReference,
+        card: SpaceCard,
+    },
+    /// The player was pushed back one space, e.g. by a laser blast.
+    SpaceCardLost {
+        player: PlayerReference,
+        card: SpaceCard,
+    },
+    /// A tether moved a space card from one row to another.
+    SpaceCardPulled {
+        from: PlayerReference,
+        to: PlayerReference,
+        card: SpaceCard,
+    },
+    SpacesSwapped {
+        player: PlayerReference,
+        with: PlayerReference,
+    },
+    PlayerDied {
+        player: PlayerReference,
+        cause: DeathCause,
+    },
+    GameWon {
+        player: PlayerReference,
+        reason: WinReason,
+    },
+}
+
+/// Who sees an event happen at the table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Audience {
+    Everyone,
+    /// Only these players, e.g. whoever drew a card, or both sides of a tractor beam.
+    Only(Vec<PlayerReference>),
+}
+
+impl Audience {
+    pub fn includes(&self, player: &PlayerReference) -> bool {
+        match self {
+            Audience::Everyone => true,
+            Audience::Only(players) => players.contains(player),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeathCause {
+    NoOxygen,
+    OxygenSiphon,
+    HoleInSuit,
+    CosmicRadiation,
+    AsteroidField,
+    /// Taken out of the game for misconduct.
+    Forfeited,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WinReason {
+    ReachedShip,
+    LastSurvivor,
+}
+
+impl GameEvent {
+    /// The player the event happened to, or who made it happen when two players are involved.
+    pub fn player(&self) -> Option<PlayerReference> {
+        match self {
+            GameEvent::GameStarted { .. } => None,
+            GameEvent::CardsShown { to, .. }
+            | GameEvent::CardStolen { to, .. }
+            | GameEvent::SpaceCardPulled { to, .. } => Some(*to),
+            GameEvent::TurnStarted { player }
+            | GameEvent::CardDrawn { player, .. }
+            | GameEvent::ActionPlayed { player, .. }
+            | GameEvent::DecisionRejected { player, .. }
+            | GameEvent::DecisionImposed { player, .. }
+            | GameEvent::ActionsSkipped { player }
+            | GameEvent::ShieldUsed { player, .. }
+            | GameEvent::CardsDiscarded { player, .. }
+            | GameEvent::ChangeGiven { player, .. }
+            | GameEvent::Breathed { player }
+            | GameEvent::Travelled { player }
+            | GameEvent::SpaceCardRevealed { player, .. }
+            | GameEvent::SpaceCardLost { player, .. }
+            | GameEvent::SpacesSwapped { player, .. }
+            | GameEvent::PlayerDied { player, .. }
+            | GameEvent::GameWon { player, .. } => Some(*player),
+        }
+    }
+}
+
+/// Receives the events from a game as they happen.
+///
+/// Closures taking `(&Game, &GameEvent)` are observers too, and see every event.
+pub trait GameObserver {
+    fn on_event(&mut self, game: &Game, event: &GameEvent);
+
+    /// Called once the events of a step have all been passed on, with the game as it now is.
+    fn on_step(&mut self, _game: &Game) {}
+
+    /// The player whose place at the table the observer watches from. It is only told about
+    /// events that player saw. None, the default, sees everything.
+    fn seat(&self) -> Option<PlayerReference> {
+        None
+    }
+}
+
+impl<F> GameObserver for F
+where
+    F: FnMut(&Game, &GameEvent),
+{
+    fn on_event(&mut self, game: &Game, event: &GameEvent) {
+        self(game, event)
+    }
+}
+
+/// Only passes on what one player saw, e.g. to show the game to a person playing at that seat.
+pub struct SeatedObserver<O> {
+    seat: PlayerReference,
+    observer: O,
+}
+
+impl<O: GameObserver> SeatedObserver<O> {
+    pub fn new(seat: PlayerReference, observer: O) -> Self {
+        Self { seat, observer }
+    }
+}
+
+impl<O: GameObserver> GameObserver for SeatedObserver<O> {
+    fn on_event(&mut self, game: &Game, event: &GameEvent) {
+        self.observer.on_event(game, event);
+    }
+
+    fn on_step(&mut self, game: &Game) {
+        self.observer.on_step(game);
+    }
+
+    fn seat(&self) -> Option<PlayerReference> {
+        Some(self.seat)
+    }
+}
+
+impl Display for GameEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEvent::GameStarted {
+                player_count,
+                ship_distance,
+                ..
+            } => write!(
+                f,
+                "A game with {} players begins. The ship is {} spaces away.",
+                player_count, ship_distance
+            ),
+            GameEvent::TurnStarted { player } => write!(f, "Player {}'s turn.", player.0),
+            GameEvent::CardDrawn { player, card } => {
+                write!(f, "Player {} picked up a {:?}.", player.0, card)
+            }
+            GameEvent::ActionPlayed { player, action } => {
+                write!(f, "Player {} played {:?}.", player.0, action)
+            }
+            GameEvent::DecisionRejected {
+                player,
+                decision,
+                reason,
+            } => write!(
+                f,
+                "Player {} tried an invalid decision, {}: {}",
+                player.0, decision, reason
+            ),
+            GameEvent::DecisionImposed { player, decision } => write!(
+                f,
+                "Player {} didn't decide in time, so the game chose: {}.",
+                player.0, decision
+            ),
+            GameEvent::ActionsSkipped { player } => write!(
+                f,
+                "Player {} can't play any actions this turn as a penalty.",
+                player.0
+            ),
+            GameEvent::ShieldUsed { player, action } => write!(
+                f,
+                "Player {} defended against {:?} with a shield.",
+                player.0, action
+            ),
+            GameEvent::CardsShown { from, to, cards } => write!(
+                f,
+                "Player {} looked at player {}'s hand and saw {:?}.",
+                to.0, from.0, cards
+            ),
+            GameEvent::CardStolen { from, to, card } => write!(
+                f,
+                "Player {} took a {:?} from player {}.",
+                to.0, card, from.0
+            ),
+            GameEvent::CardsDiscarded { player, cards } => {
+                write!(f, "Player {} discarded {:?}.", player.0, cards)
+            }
+            GameEvent::ChangeGiven { player, cards } => {
+                write!(f, "Player {} got {:?} in change.", player.0, cards)
+            }
+            GameEvent::Breathed { player } => write!(f, "Player {} breathed.", player.0),
+            GameEvent::Travelled { player } => write!(f, "Player {} travelled.", player.0),
+            GameEvent::SpaceCardRevealed { player, card } => {
+                write!(f, "Player {} got {:?}.", player.0, card)
+            }
+            GameEvent::SpaceCardLost { player, card } => write!(
+                f,
+                "Player {} lost their {:?} and moved back one space.",
+                player.0, card
+            ),
+            GameEvent::SpaceCardPulled { from, to, card } => write!(
+                f,
+                "Player {} tethered player {} and pulled their {:?} across.",
+                to.0, from.0, card
+            ),
+            GameEvent::SpacesSwapped { player, with } => write!(
+                f,
+                "Player {} swapped spaces with player {}.",
+                player.0, with.0
+            ),
+            GameEvent::PlayerDied { player, cause } => {
+                write!(f, "Player {} died because they {}.", player.0, cause)
+            }
+            GameEvent::GameWon { player, reason } => match reason {
+                WinReason::ReachedShip => {
+                    write!(f, "Player {} reached the ship and won!", player.0)
+                }
+                WinReason::LastSurvivor => {
+                    write!(f, "Player {} is the last one alive and won!", player.0)
+                }
+            },
+        }
+    }
+}
+
+impl Display for DeathCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            DeathCause::NoOxygen => "didn't have any oxygen cards left",
+            DeathCause::OxygenSiphon => "had their oxygen siphoned",
+            DeathCause::HoleInSuit => "had a hole in their suit",
+            DeathCause::CosmicRadiation => "were hit by cosmic radiation",
+            DeathCause::AsteroidField => "flew through an asteroid field",
+            DeathCause::Forfeited => "kept breaking the rules and forfeited",
+        };
+        f.write_str(text)
+    }
+}
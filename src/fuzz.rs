@@ -0,0 +1,565 @@
+use crate::actions::BreatheOrTravel;
+use crate::decision::{Decision, PendingDecision};
+use crate::tournament::seat_seed;
+use crate::visible_state::VisibleState;
+use crate::{
+    Action, GameCard, GameState, InvariantError, Phase, PlayerController, PlayerReference,
+    RandomPlayerController, RuleError, RuleSet, SpaceCard,
+};
+use miette::Diagnostic;
+use rand::prelude::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use thiserror::Error;
+
+const GAME_CARDS: [GameCard; 10] = [
+    GameCard::O1,
+    GameCard::O2,
+    GameCard::OxygenSiphon,
+    GameCard::Shield,
+    GameCard::HackSuit,
+    GameCard::TractorBeam,
+    GameCard::RocketBooster,
+    GameCard::LaserBlast,
+    GameCard::HoleInSuit,
+    GameCard::Tether,
+];
+
+/// A controller that tries to break the engine.
+///
+/// Some of the time it plays like a `RandomPlayerController`. The rest of the time it answers
+/// with garbage: cards it doesn't hold, players that don't exist or are dead, the wrong number of
+/// cards to discard, or an answer to a different question altogether.
+pub struct AdversarialController {
+    rng: ChaCha8Rng,
+    honest: RandomPlayerController,
+    visible_state: VisibleState,
+    chaos: f64,
+}
+
+impl AdversarialController {
+    /// `chaos` is the chance of answering with garbage, from 0 to 1.
+    pub fn new(seed: u64, chaos: f64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            honest: RandomPlayerController::with_seed(seed.wrapping_add(1)),
+            visible_state: VisibleState::invalid(),
+            chaos: chaos.clamp(0.0, 1.0),
+        }
+    }
+
+    /// An answer that is probably, but not certainly, invalid.
+    fn garbage(&mut self) -> Decision {
+        let player_count = self.visible_state.players.len();
+        // Up to two seats past the end of the table, which don't exist.
+        let anyone = PlayerReference(self.rng.gen_range(0..player_count + 3));
+        let card = *GAME_CARDS.choose(&mut self.rng).unwrap();
+
+        match self.rng.gen_range(0..9) {
+            0 => Decision::PlayAction(Action::from_card(card, anyone)),
+            1 => Decision::PlayAction(Action::from_card(card, self.visible_state.viewer)),
+            2 => {
+                let count = self.rng.gen_range(0..5);
+                let cards = (0..count)
+                    .map(|_| *GAME_CARDS.choose(&mut self.rng).unwrap())
+                    .collect();
+                Decision::ForcedDiscard(cards)
+            }
+            3 => Decision::SwapWith(anyone),
+            4 => Decision::TakeCard(card),
+            5 if self.rng.gen() => Decision::BreatheOrTravel(BreatheOrTravel::Breathe),
+            5 => Decision::BreatheOrTravel(BreatheOrTravel::Travel),
+            6 => Decision::Defend(self.rng.gen()),
+            7 => {
+                let count = self.rng.gen_range(0..3);
+                let cards = (0..count)
+                    .map(|_| *[GameCard::O1, GameCard::O2].choose(&mut self.rng).unwrap())
+                    .collect();
+                Decision::PayOxygen(cards)
+            }
+            _ => Decision::PlayAction(None),
+        }
+    }
+}
+
+impl PlayerController for AdversarialController {
+    fn update_state(&mut self, visible_state: VisibleState) {
+        self.honest.update_state(visible_state.clone());
+        self.visible_state = visible_state;
+    }
+
+    fn play_action(&mut self) -> Option<Action> {
+        self.honest.play_action()
+    }
+
+    fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        self.honest.breathe_or_travel()
+    }
+
+    fn defend(&mut self, action: &Action) -> bool {
+        self.honest.defend(action)
+    }
+
+    fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+        self.honest.forced_discard(card_count)
+    }
+
+    fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+        self.honest.choose_player_to_swap_with()
+    }
+
+    fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+        self.honest.choose_card_to_take(options)
+    }
+
+    fn pay_oxygen(&mut self, cost: usize, options: Vec<Vec<GameCard>>) -> Vec<GameCard> {
+        self.honest.pay_oxygen(cost, options)
+    }
+
+    fn decide(&mut self, pending: &PendingDecision) -> Decision {
+        if self.rng.gen_bool(self.chaos) {
+            self.garbage()
+        } else {
+            self.honest.decide(pending)
+        }
+    }
+}
+
+/// Which games a `Fuzzer` plays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzConfig {
+    pub games: usize,
+
+    /// Game N is played with this seed plus N.
+    pub seed: u64,
+
+    /// Game N is played with the Nth of these, round and round.
+    pub player_counts: Vec<usize>,
+
+    /// The chance of each answer from an `AdversarialController` being garbage.
+    pub chaos: f64,
+
+    /// Decisions the engine may accept in one game before it counts as never ending.
+    pub max_decisions: usize,
+
+    pub rules: RuleSet,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            games: 1000,
+            seed: 0,
+            player_counts: (2..=6).collect(),
+            chaos: 0.3,
+            max_decisions: 10_000,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum FuzzError {
+    #[error("Fuzzing needs at least one player count.")]
+    NoPlayerCounts,
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Rules(#[from] RuleError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Failed(#[from] Box<FuzzFailure>),
+}
+
+/// What went wrong in a failing game.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum FuzzFailureKind {
+    #[error("The engine panicked: {0}")]
+    Panic(String),
+
+    #[error("The game was still going after {0} decisions.")]
+    Timeout(usize),
+
+    #[error("The engine failed: {0}")]
+    Engine(String),
+
+    #[error(transparent)]
+    Invariant(InvariantError),
+
+    #[error("Rejecting \"{decision}\" changed the game. The error was: {error}")]
+    RejectionChangedState { decision: Decision, error: String },
+
+    #[error("The engine accepted \"{0}\", which isn't one of its legal decisions.")]
+    IllegalDecisionAccepted(Decision),
+
+    #[error("The engine rejected \"{decision}\", which is one of its legal decisions: {error}")]
+    LegalDecisionRejected { decision: Decision, error: String },
+
+    #[error("The engine has no legal decisions for: {0}")]
+    NoLegalDecision(PendingDecision),
+}
+
+/// A game that broke the engine, cut down to the fewest decisions that still break it.
+#[derive(Error, Debug, Clone, PartialEq, Diagnostic)]
+#[error("Seed {seed} with {player_count} players failed after {} decisions. {kind}", .decisions.len())]
+#[diagnostic(help("{}", self.reproduction()))]
+pub struct FuzzFailure {
+    pub seed: u64,
+    pub player_count: usize,
+    pub kind: FuzzFailureKind,
+
+    /// Every decision submitted to the engine, in order, including the rejected ones.
+    pub decisions: Vec<Decision>,
+}
+
+impl FuzzFailure {
+    /// How to play the failing game again.
+    pub fn reproduction(&self) -> String {
+        let mut text = format!(
+            "Submit these to GameState::with_rules(Some({}), {}, ...) in order:",
+            self.seed, self.player_count
+        );
+        for (idx, decision) in self.decisions.iter().enumerate() {
+            let json = serde_json::to_string(decision).unwrap_or_default();
+            text.push_str(&format!("\n{:>4}. {}", idx + 1, json));
+        }
+        text
+    }
+}
+
+/// How a fuzzing run went, when nothing broke.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzReport {
+    pub games: usize,
+    pub accepted: usize,
+    pub rejected: usize,
+    pub longest_game: usize,
+}
+
+impl Display for FuzzReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Played {} games without a problem. The engine accepted {} decisions and rejected {}, \
+             with {} in the longest game.",
+            self.games, self.accepted, self.rejected, self.longest_game
+        )
+    }
+}
+
+/// Plays seeded games with `AdversarialController`s in every seat, checking after every decision
+/// that the engine:
+///
+/// * Doesn't panic, and finishes every game.
+/// * Accepts exactly the decisions it lists as legal.
+/// * Leaves the game untouched when it rejects a decision.
+/// * Keeps its invariants, see `GameState::check_invariants`.
+///
+/// Whenever a decision is rejected, a legal one is picked at random so that the game goes on.
+pub struct Fuzzer {
+    config: FuzzConfig,
+}
+
+/// Everything submitted in one game, and how it ended.
+#[derive(Default)]
+struct Trial {
+    decisions: Vec<Decision>,
+    accepted: usize,
+    rejected: usize,
+}
+
+impl Fuzzer {
+    pub fn new(config: FuzzConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &FuzzConfig {
+        &self.config
+    }
+
+    /// Play every game, stopping at the first failure.
+    pub fn run(&self) -> Result<FuzzReport, FuzzError> {
+        if self.config.player_counts.is_empty() {
+            return Err(FuzzError::NoPlayerCounts);
+        }
+        for player_count in &self.config.player_counts {
+            self.config.rules.check(*player_count)?;
+        }
+
+        let mut report = FuzzReport::default();
+        for idx in 0..self.config.games {
+            let seed = self.config.seed.wrapping_add(idx as u64);
+            let player_count = self.config.player_counts[idx % self.config.player_counts.len()];
+            let mut trial = Trial::default();
+            if let Err(kind) = self.trial(seed, player_count, None, &mut trial) {
+                let decisions = self.minimize(seed, player_count, &kind, trial.decisions);
+                return Err(Box::new(FuzzFailure {
+                    seed,
+                    player_count,
+                    kind,
+                    decisions,
+                })
+                .into());
+            }
+
+            report.games += 1;
+            report.accepted += trial.accepted;
+            report.rejected += trial.rejected;
+            report.longest_game = report.longest_game.max(trial.accepted);
+        }
+        Ok(report)
+    }
+
+    /// Play one game, either with adversarial controllers or by submitting the script.
+    fn trial(
+        &self,
+        seed: u64,
+        player_count: usize,
+        script: Option<&[Decision]>,
+        trial: &mut Trial,
+    ) -> Result<(), FuzzFailureKind> {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            self.play(seed, player_count, script, trial)
+        }));
+        match result {
+            Ok(result) => result,
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(FuzzFailureKind::Panic(message))
+            }
+        }
+    }
+
+    fn play(
+        &self,
+        seed: u64,
+        player_count: usize,
+        script: Option<&[Decision]>,
+        trial: &mut Trial,
+    ) -> Result<(), FuzzFailureKind> {
+        let engine = |err: miette::Report| FuzzFailureKind::Engine(err.to_string());
+
+        let mut state = GameState::with_rules(Some(seed), player_count, self.config.rules.clone())
+            .map_err(|err| engine(err.into()))?;
+        let mut controllers: Vec<AdversarialController> = (0..player_count)
+            .map(|seat| AdversarialController::new(seat_seed(seed, seat), self.config.chaos))
+            .collect();
+        let mut fallback_rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut script = script.map(|script| script.iter());
+        let mut fallback = None;
+
+        let mut pending = state.start().map_err(engine)?;
+        state
+            .check_invariants()
+            .map_err(FuzzFailureKind::Invariant)?;
+
+        while let Some(current) = pending.clone() {
+            if trial.accepted >= self.config.max_decisions {
+                return Err(FuzzFailureKind::Timeout(trial.accepted));
+            }
+
+            let decision = match (fallback.take(), &mut script) {
+                (Some(decision), _) => decision,
+                (None, Some(script)) => match script.next() {
+                    None => return Ok(()),
+                    Some(decision) => decision.clone(),
+                },
+                (None, None) => {
+                    let player = current.player();
+                    let controller = &mut controllers[player.0];
+                    controller.update_state(state.visible_state(&player).map_err(engine)?);
+                    controller.decide(&current)
+                }
+            };
+            trial.decisions.push(decision.clone());
+
+            let legal: Vec<Decision> = state.legal_decisions().iter().map(normalized).collect();
+            let is_legal = legal.contains(&normalized(&decision));
+            let before = Fingerprint::of(&state);
+            match state.apply(decision.clone()) {
+                Ok(next) => {
+                    if !is_legal {
+                        return Err(FuzzFailureKind::IllegalDecisionAccepted(decision));
+                    }
+                    trial.accepted += 1;
+                    pending = next;
+                }
+                Err(err) => {
+                    let error = err.to_string();
+                    if is_legal {
+                        return Err(FuzzFailureKind::LegalDecisionRejected { decision, error });
+                    }
+                    if Fingerprint::of(&state) != before {
+                        return Err(FuzzFailureKind::RejectionChangedState { decision, error });
+                    }
+                    trial.rejected += 1;
+
+                    if script.is_none() {
+                        match legal.choose(&mut fallback_rng) {
+                            None => return Err(FuzzFailureKind::NoLegalDecision(current)),
+                            Some(decision) => fallback = Some(decision.clone()),
+                        }
+                    }
+                }
+            }
+
+            state
+                .check_invariants()
+                .map_err(FuzzFailureKind::Invariant)?;
+        }
+        Ok(())
+    }
+
+    /// Drop as many decisions as possible while the game still fails the same way, a chunk at a
+    /// time, halving the chunks until single decisions are tried.
+    fn minimize(
+        &self,
+        seed: u64,
+        player_count: usize,
+        kind: &FuzzFailureKind,
+        mut decisions: Vec<Decision>,
+    ) -> Vec<Decision> {
+        let fails = |script: &[Decision]| {
+            let result = self.trial(seed, player_count, Some(script), &mut Trial::default());
+            matches!(result, Err(other) if std::mem::discriminant(&other) == std::mem::discriminant(kind))
+        };
+
+        let mut chunk = decisions.len().div_ceil(2);
+        while chunk > 0 {
+            let mut start = 0;
+            while start < decisions.len() {
+                let end = (start + chunk).min(decisions.len());
+                let mut candidate = decisions[..start].to_vec();
+                candidate.extend_from_slice(&decisions[end..]);
+                if fails(&candidate) {
+                    decisions = candidate;
+                } else {
+                    start = end;
+                }
+            }
+            chunk /= 2;
+        }
+        decisions
+    }
+}
+
+/// Discards and payments are legal in any order, so compare them sorted.
+fn normalized(decision: &Decision) -> Decision {
+    match decision {
+        Decision::ForcedDiscard(cards) => {
+            let mut cards = cards.clone();
+            cards.sort();
+            Decision::ForcedDiscard(cards)
+        }
+        Decision::PayOxygen(cards) => {
+            let mut cards = cards.clone();
+            cards.sort();
+            Decision::PayOxygen(cards)
+        }
+        decision => decision.clone(),
+    }
+}
+
+/// Everything a rejected decision could have wrongly changed, down to the order of the decks and
+/// the dice still to be rolled.
+#[derive(PartialEq)]
+struct Fingerprint {
+    pending: Option<PendingDecision>,
+    phase: Phase,
+    whose_turn: PlayerReference,
+    winner: Option<PlayerReference>,
+    players: Vec<(bool, Vec<GameCard>, Vec<SpaceCard>)>,
+    deck: Vec<GameCard>,
+    discard_pile: Vec<GameCard>,
+    space_deck: Vec<SpaceCard>,
+    space_discard_pile: Vec<SpaceCard>,
+    rng: ChaCha8Rng,
+}
+
+impl Fingerprint {
+    fn of(state: &GameState) -> Self {
+        let players = (0..state.player_count())
+            .filter_map(|idx| state.player(&PlayerReference(idx)).ok())
+            .map(|player| (player.alive, player.hand.clone(), player.space.clone()))
+            .collect();
+        Self {
+            pending: state.pending_decision().cloned(),
+            phase: state.phase(),
+            whose_turn: state.whose_turn(),
+            winner: state.winner(),
+            players,
+            deck: state.deck().to_vec(),
+            discard_pile: state.discard_pile().to_vec(),
+            space_deck: state.space_deck().0.to_vec(),
+            space_discard_pile: state.space_deck().1.to_vec(),
+            rng: state.rng().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzz() {
+        let fuzzer = Fuzzer::new(FuzzConfig {
+            games: 1000,
+            ..FuzzConfig::default()
+        });
+        match fuzzer.run() {
+            Ok(report) => {
+                assert_eq!(report.games, 1000);
+                assert!(report.rejected > 0);
+            }
+            Err(err) => panic!("{:?}", miette::Report::new(err)),
+        }
+    }
+
+    #[test]
+    fn test_fuzz_mixed_payments() {
+        // Costs of 3 can be paid with an O1 and an O2, in either order.
+        let fuzzer = Fuzzer::new(FuzzConfig {
+            games: 300,
+            chaos: 0.5,
+            rules: RuleSet {
+                asteroid_field_damage: 3,
+                oxygen_siphon_count: 3,
+                ..RuleSet::default()
+            },
+            ..FuzzConfig::default()
+        });
+        if let Err(err) = fuzzer.run() {
+            panic!("{:?}", miette::Report::new(err));
+        }
+    }
+
+    #[test]
+    fn test_minimizes_failures() {
+        // Any game that lasts more than a few decisions counts as a failure.
+        let fuzzer = Fuzzer::new(FuzzConfig {
+            games: 1,
+            seed: 4,
+            player_counts: vec![3],
+            chaos: 0.5,
+            max_decisions: 5,
+            ..FuzzConfig::default()
+        });
+        let Err(FuzzError::Failed(failure)) = fuzzer.run() else {
+            panic!("expected the game to time out");
+        };
+        assert_eq!(failure.kind, FuzzFailureKind::Timeout(5));
+        // Only the accepted decisions are needed to get that far.
+        assert_eq!(failure.decisions.len(), 5);
+        assert_eq!(failure.seed, 4);
+        assert!(failure.reproduction().contains("with_rules(Some(4), 3"));
+    }
+}
@@ -1,582 +1,481 @@
-use crate::actions::BreatheOrTravel;
+use crate::decision::{Decision, PendingDecision};
 use crate::errors::SelfishError;
+use crate::events::{Audience, GameEvent, GameObserver};
+use crate::misconduct::{fallback_decision, MisconductPolicy, Penalty};
 use crate::player_controller::PlayerController;
 use crate::visible_state::VisibleState;
-use crate::{Action, GameCard, GameDeck, Player, SpaceCard, SpaceDeck};
-use miette::{bail, WrapErr};
-use owo_colors::{CssColors, DynColors, OwoColorize};
-use rand_chacha::rand_core::SeedableRng;
-use rand_chacha::ChaCha8Rng;
-use std::mem::swap;
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+use crate::{
+    Action, BreatheOrTravel, GameCard, GameState, InvariantError, Player, RuleError, RuleSet,
+};
+use miette::bail;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct PlayerReference(pub usize);
 
+/// A `GameState` played by one controller per seat, with observers watching.
+///
+/// Either let the controllers play the whole game with `simulate`, or drive it yourself one
+/// decision at a time with `start` and then `step` or `submit`.
 pub struct Game {
-    rng: ChaCha8Rng,
-    game_over: bool,
-    game_deck: GameDeck,
-    space_deck: SpaceDeck,
-    players: Vec<Player>,
-    controllers: Vec<Box<dyn PlayerController>>,
-    pub whose_turn_reference: PlayerReference,
-    phase: Phase,
+    state: GameState,
+    controllers: Vec<Option<Box<dyn PlayerController>>>,
+    observers: Vec<Box<dyn GameObserver>>,
+    misconduct_policy: MisconductPolicy,
+    /// Seats that lose their next action phase.
+    penalized: Vec<bool>,
+    /// Seats that have forfeited, but are still in the game until it is between actions.
+    forfeiting: Vec<bool>,
 }
 
 impl Game {
+    /// Without a seed one is picked at random, which can still be read back with `seed`.
+    ///
+    /// Panics for a table the official rules can't be played at, like `GameState::new`.
+    /// `with_rules` returns the error instead.
     pub fn new(seed: Option<u64>, controllers: Vec<Box<dyn PlayerController>>) -> Game {
-        let mut rng = match seed {
-            None => ChaCha8Rng::from_entropy(),
-            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
-        };
+        let state = GameState::new(seed, controllers.len());
+        Self::from_state(state, controllers)
+    }
 
-        let space_deck = SpaceDeck::shuffled(&mut rng);
-        let mut game_deck = GameDeck::shuffled(&mut rng);
-        let mut players = Vec::new();
-        for _ in 0..controllers.len() {
-            let mut player = Player::new();
+    /// Like `new`, but played by a variant of the rules, e.g. loaded with `RuleSet::load`.
+    pub fn with_rules(
+        seed: Option<u64>,
+        rules: RuleSet,
+        controllers: Vec<Box<dyn PlayerController>>,
+    ) -> Result<Game, RuleError> {
+        let state = GameState::with_rules(seed, controllers.len(), rules)?;
+        Ok(Self::from_state(state, controllers))
+    }
 
-            player.give(game_deck.take(GameCard::O2));
-            for _ in 0..4 {
-                player.give(game_deck.take(GameCard::O1));
-            }
+    /// A game without controllers, where every decision is passed in with `submit`.
+    ///
+    /// Panics for a table the official rules can't be played at, like `GameState::new`.
+    pub fn headless(seed: Option<u64>, player_count: usize) -> Game {
+        Self::from_state(GameState::new(seed, player_count), Vec::new())
+    }
 
-            players.push(player);
-        }
+    /// A game where only some seats have a controller, and the others are answered with
+    /// `submit`, e.g. a person playing through a UI.
+    ///
+    /// Panics for a table the official rules can't be played at, like `GameState::new`.
+    pub fn with_seats(
+        seed: Option<u64>,
+        controllers: Vec<Option<Box<dyn PlayerController>>>,
+    ) -> Game {
+        Self::from_state_with_seats(GameState::new(seed, controllers.len()), controllers)
+    }
 
+    /// Like `with_seats`, from an existing state.
+    pub fn from_state_with_seats(
+        state: GameState,
+        controllers: Vec<Option<Box<dyn PlayerController>>>,
+    ) -> Game {
+        let player_count = state.player_count();
         Game {
-            rng,
-            game_deck,
-            space_deck,
-            players,
+            state,
             controllers,
-            whose_turn_reference: PlayerReference(0),
-            phase: Phase::Pickup,
-            game_over: false,
+            observers: Vec::new(),
+            misconduct_policy: MisconductPolicy::default(),
+            penalized: vec![false; player_count],
+            forfeiting: vec![false; player_count],
         }
     }
 
-    pub fn player_count(&self) -> usize {
-        self.players.len()
+    /// Play on from an existing state, e.g. one that was forked from another game.
+    pub fn from_state(state: GameState, controllers: Vec<Box<dyn PlayerController>>) -> Game {
+        Self::from_state_with_seats(state, controllers.into_iter().map(Some).collect())
     }
 
-    pub fn simulate(&mut self) -> miette::Result<()> {
-        while !self.game_over {
-            if !self.current_player().alive {
-                self.next_player();
-                continue;
-            }
-
-            // Always start at the pickup phase.
-            let card = self.draw_card_phase();
-            self.log(format!("Player picked up a {:?}.", card));
-
-            // Keep asking the controller for an action until they don't want to do any more.
-            loop {
-                if self.current_player().in_solar_flare() {
-                    break;
-                }
-
-                let visible_state = self.visible_state()?;
-                let controller = self.current_controller()?;
-                controller.update_state(visible_state);
-                let action = match controller.play_action() {
-                    None => {
-                        break;
-                    }
-                    Some(action) => action,
-                };
-
-                if let Err(err) = self.action(action) {
-                    self.log(format!("Controller tried to do an invalid action: {}", err));
-
-                    // We are not patient enough for controllers that don't know how to play, so
-                    // let us immediately move to the BreatheOrTravel phase.
-                    break;
-                }
-            }
-
-            self.breathe_or_travel()?;
-        }
+    /// The rules state, which can be cloned to explore other moves.
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
 
-        Ok(())
+    pub fn rules(&self) -> &RuleSet {
+        self.state.rules()
     }
 
-    fn visible_state(&self) -> miette::Result<VisibleState> {
-        VisibleState::try_from_game(self)
+    /// How many space cards a player needs in front of them to reach the ship.
+    pub fn default_ship_distance(player_count: usize) -> usize {
+        GameState::default_ship_distance(player_count)
     }
 
-    fn next_player(&mut self) {
-        let next_player = (self.whose_turn_reference.0 + 1) % self.players.len();
-        self.whose_turn_reference = PlayerReference(next_player);
+    /// The seed that makes this game reproducible, given the same controller decisions.
+    pub fn seed(&self) -> u64 {
+        self.state.seed()
     }
 
-    /// If the player has no oxygen cards, they die.
-    /// If the player only has either an O1 or an O2, they are automatically played without
-    /// asking the controller.
-    /// Otherwise, ask the controller to play a O1 or a O2.
-    pub fn breathe_or_travel(&mut self) -> miette::Result<()> {
-        assert_eq!(self.phase, Phase::Actions);
-        self.phase = Phase::BreatheOrTravel;
+    pub fn ship_distance(&self) -> usize {
+        self.state.ship_distance()
+    }
 
-        let whose_turn_reference = self.whose_turn_reference;
+    pub fn misconduct_policy(&self) -> MisconductPolicy {
+        self.misconduct_policy
+    }
 
-        let player = self.player_mut(&whose_turn_reference)?;
-        let has_o1 = player.has_card(&GameCard::O1);
-        let has_o2 = player.has_card(&GameCard::O2);
+    /// Change how `step` deals with controllers that answer with something the rules don't
+    /// allow. By default their answer is replaced with `fallback_decision` straight away.
+    pub fn set_misconduct_policy(&mut self, misconduct_policy: MisconductPolicy) {
+        self.misconduct_policy = misconduct_policy;
+    }
 
-        let breathe_or_travel: Option<BreatheOrTravel> = match (has_o1, has_o2) {
-            (false, false) => None,
-            (true, false) => Some(BreatheOrTravel::Breathe),
-            (false, true) => Some(BreatheOrTravel::Travel),
-            (true, true) => {
-                // The player has both an O1 and an O2, so ask the controller to play one.
-                Some(self.current_controller()?.breathe_or_travel())
-            }
-        };
+    /// Check the engine's invariants after every step, see `GameState::check_invariants`.
+    pub fn set_check_invariants(&mut self, check_invariants: bool) {
+        self.state.set_check_invariants(check_invariants);
+    }
 
-        // Player is re-borrowed here because the controller needed it.
-        let player = self.player_mut(&whose_turn_reference)?;
-        match breathe_or_travel {
-            Some(BreatheOrTravel::Breathe) => {
-                player.remove_card(&GameCard::O1).wrap_err("Breathing.")?;
-                self.game_deck.add_to_discard(GameCard::O1);
-                self.log("Player breathed.".to_string());
-            }
-            Some(BreatheOrTravel::Travel) => {
-                player.remove_card(&GameCard::O2).wrap_err("Travelling.")?;
-                self.game_deck.add_to_discard(GameCard::O1);
-                self.log("Player travelled.".to_string());
-                self.add_space()?;
-            }
-            None => {
-                self.player_died(
-                    &whose_turn_reference,
-                    "they didn't have any oxygen cards left.",
-                )?;
-            }
-        }
+    pub fn whose_turn(&self) -> PlayerReference {
+        self.state.whose_turn()
+    }
 
-        self.phase = Phase::Pickup;
-        self.next_player();
+    pub fn player_count(&self) -> usize {
+        self.state.player_count()
+    }
 
-        Ok(())
+    pub fn player(&self, player_reference: &PlayerReference) -> miette::Result<&Player> {
+        self.state.player(player_reference)
     }
 
-    pub fn player_died(
-        &mut self,
-        player_reference: &PlayerReference,
-        reason: &str,
-    ) -> miette::Result<()> {
-        let player = self.player_mut(player_reference)?;
-        player.alive = false;
-        self.log(format!(
-            "Player {} died because {}.",
-            player_reference.0, reason
-        ));
+    /// The player who reached the ship, or the last player alive.
+    pub fn winner(&self) -> Option<PlayerReference> {
+        self.state.winner()
+    }
 
-        self.check_game_over();
+    /// Subscribe to events from now on, e.g. a `TerminalObserver` to watch the game. Observers
+    /// with a seat only hear about what that player saw.
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
 
-        self.next_player();
-        Ok(())
+    /// Start the game, returning the first decision it is waiting on.
+    pub fn start(&mut self) -> miette::Result<Option<PendingDecision>> {
+        let pending = self.state.start()?;
+        self.flush_events();
+        Ok(pending)
     }
 
-    pub fn check_game_over(&mut self) {
-        let mut alive_count = 0;
-        for player in &self.players {
-            if player.alive {
-                alive_count += 1;
-            }
-        }
+    /// What the game is waiting on. None before `start` and once the game is over.
+    pub fn pending_decision(&self) -> Option<&PendingDecision> {
+        self.state.pending_decision()
+    }
 
-        println!("Alive count: {}", alive_count);
+    /// Answer the pending decision yourself instead of asking a controller, e.g. from a UI.
+    ///
+    /// An invalid decision is an error and the game keeps waiting for a valid one.
+    pub fn submit(&mut self, decision: Decision) -> miette::Result<Option<PendingDecision>> {
+        self.state.apply(decision)?;
+        self.flush_events();
+        self.settle_forfeits()?;
+        Ok(self.state.pending_decision().cloned())
+    }
 
-        if alive_count == 1 {
-            self.log("Game over!".to_string());
-            self.game_over = true;
-        }
+    /// Whether the seat is played by a controller, rather than answered with `submit`.
+    pub fn has_controller(&self, player_reference: &PlayerReference) -> bool {
+        matches!(self.controllers.get(player_reference.0), Some(Some(_)))
     }
 
-    pub fn add_space(&mut self) -> miette::Result<()> {
-        let whose_turn_reference = self.whose_turn_reference;
-        let space_card = self.space_deck.draw();
+    /// Ask the controller of the seat that the game is waiting on, and submit their answer.
+    ///
+    /// Answers the rules don't allow are dealt with by the `MisconductPolicy`.
+    pub fn step(&mut self) -> miette::Result<Option<PendingDecision>> {
+        let pending = match self.state.pending_decision() {
+            None => bail!(SelfishError::NoDecisionPending),
+            Some(pending) => pending.clone(),
+        };
+        let player = pending.player();
 
-        let player = self.player_mut(&whose_turn_reference)?;
-        player.space.push(space_card.clone());
+        if self.forfeiting[player.0] {
+            return self.impose(player);
+        }
+        if self.penalized[player.0] && matches!(pending, PendingDecision::PlayAction { .. }) {
+            self.penalized[player.0] = false;
+            self.emit(GameEvent::ActionsSkipped { player });
+            return self.submit(Decision::PlayAction(None));
+        }
 
-        match &space_card {
-            SpaceCard::BlankSpace => {
-                self.log("Player got blank space.".to_string());
-            }
-            SpaceCard::UsefulJunk => {
-                let card = self.draw_card();
-                self.log(format!(
-                    "Player got {:?} and picked up a {:?}.",
-                    space_card, card,
-                ));
-            }
-            SpaceCard::MysteriousNebula => {
-                let card_1 = self.draw_card();
-                let card_2 = self.draw_card();
-                self.log(format!(
-                    "Player got {:?} and picked up a {:?} and a {:?}.",
-                    space_card, card_1, card_2
-                ));
-            }
-            SpaceCard::Hyperspace => {
-                self.log("Player got a hyperspace jump.".to_string());
-                self.add_space()?;
-            }
-            SpaceCard::Meteoroid => {
-                if self.current_player().hand.len() > 6 {
-                    // TODO: Ask the controller to discard two cards.
-                    let controller = self.current_controller()?;
-                    let cards = controller.forced_discard(2);
-                    if cards.len() != 2 {
-                        return Err(SelfishError::InvalidDiscardCount {
-                            expected: 2,
-                            actual: cards.len(),
-                        }
-                        .into());
-                    }
-                    for card in &cards {
-                        let player = self.current_player();
-                        player.remove_card(card).wrap_err("Meteoroid.")?;
-                        self.game_deck.add_to_discard(*card);
-                    }
-
-                    self.log(format!(
-                        "Player got hit by a meteoroid and had to discard {:?}.",
-                        cards
-                    ));
-                } else {
-                    self.log("Player got hit by a meteoroid but had 6 or less cards.".to_string());
-                }
-            }
-            SpaceCard::CosmicRadiation => {
-                // The player must discard an oxygen to survive.
-                self.discard_or_die(GameCard::O1, "cosmic radiation")?;
-            }
-            SpaceCard::AsteroidField => {
-                for _ in 0..2 {
-                    self.discard_or_die(GameCard::O1, "asteroid field")?;
-                }
-            }
-            SpaceCard::GravitationalAnomaly => {
-                self.log(
-                    "Player got a gravitational anomaly and moved back one space.".to_string(),
-                );
-                let player = self.current_player();
-                player.space.pop();
-            }
-            SpaceCard::WormHole => {
-                let controller = self.current_controller()?;
-                let target_reference = controller.choose_player_to_swap_with();
-                let whose_turn_reference = self.whose_turn_reference;
-                self.swap_space(&whose_turn_reference, &target_reference)?;
-                self.log(format!(
-                    "Player got a wormhole and swapped spaces with player {}.",
-                    target_reference.0
-                ));
-            }
-            SpaceCard::SolarFlare => {
-                // Nothing happens except that they can't use action cards.
+        for _ in 0..=self.misconduct_policy.retries {
+            let decision = self.ask(&pending)?;
+            match self.submit(decision.clone()) {
+                Ok(pending) => return Ok(pending),
+                // A broken invariant means the decision was played, and the engine got it wrong.
+                Err(err) if err.downcast_ref::<InvariantError>().is_some() => return Err(err),
+                Err(err) => self.emit_to(
+                    &decision_audience(&pending),
+                    GameEvent::DecisionRejected {
+                        player,
+                        decision,
+                        reason: err.to_string(),
+                    },
+                ),
             }
         }
 
-        Ok(())
-    }
-
-    pub fn swap_space(&mut self, p1: &PlayerReference, p2: &PlayerReference) -> miette::Result<()> {
-        let p1_space = self.player(p1)?.space.clone();
-        let p2_space = self.player(p2)?.space.clone();
-
-        self.player_mut(p1)?.space = p2_space;
-        self.player_mut(p2)?.space = p1_space;
-
-        Ok(())
+        match self.misconduct_policy.penalty {
+            Penalty::Fallback => {}
+            Penalty::Penalize => self.penalized[player.0] = true,
+            Penalty::Forfeit => self.forfeiting[player.0] = true,
+        }
+        self.impose(player)
     }
 
-    pub fn discard_or_die(&mut self, card: GameCard, reason: &str) -> miette::Result<()> {
-        let whose_turn_reference = self.whose_turn_reference;
-        let player = self.current_player();
-
-        // TODO: Automatically try to swap an O2 for two O1's.
-        if player.has_card(&card) {
-            player.remove_card(&card)?;
-            self.game_deck.add_to_discard(card);
-            self.log("Player survived cosmic radiation.".to_string());
-        } else {
-            self.player_died(&whose_turn_reference, reason)?;
+    /// Play the whole game with the controllers.
+    pub fn simulate(&mut self) -> miette::Result<()> {
+        let mut pending = self.start()?;
+        while pending.is_some() {
+            pending = self.step()?;
         }
         Ok(())
     }
 
-    pub fn color(&self, player_reference: &PlayerReference) -> DynColors {
-        let colors: [DynColors; 6] = [
-            "#B83AF1", "#6EB122", "#DAAC06", "#00938A", "#E23838", "#A23450",
-        ]
-        .map(|color| color.parse().unwrap());
-
-        colors[player_reference.0]
+    /// Every action the player could play right now. Empty if it isn't their action phase.
+    pub fn legal_actions(&self, player_reference: &PlayerReference) -> Vec<Action> {
+        self.state.legal_actions(player_reference)
     }
 
-    pub fn log(&self, note: String) {
-        println!("\n{}", note.color(self.color(&self.whose_turn_reference)));
-        self.print();
+    /// What the player can do at the end of their turn. Empty if it isn't their turn.
+    pub fn legal_breathe_or_travel(
+        &self,
+        player_reference: &PlayerReference,
+    ) -> Vec<BreatheOrTravel> {
+        self.state.legal_breathe_or_travel(player_reference)
     }
 
-    pub fn print(&self) {
-        for (idx, player) in self.players.iter().enumerate() {
-            let is_turn = self.whose_turn_reference == PlayerReference(idx);
-            let prefix = if !player.alive {
-                " x "
-            } else if is_turn {
-                "-->"
-            } else {
-                "   "
-            };
+    /// Who the player can swap with when they are on a wormhole.
+    pub fn legal_swap_targets(&self, player_reference: &PlayerReference) -> Vec<PlayerReference> {
+        self.state.legal_swap_targets(player_reference)
+    }
 
-            let color = if !player.alive {
-                CssColors::OrangeRed
-            } else if is_turn {
-                CssColors::White
-            } else {
-                CssColors::Grey
-            };
-            println!(" {} {:?}", prefix, player.color(color));
-        }
+    /// The ways the player can discard when a meteoroid has hit them.
+    pub fn legal_discards(&self, player_reference: &PlayerReference) -> Vec<Vec<GameCard>> {
+        self.state.legal_discards(player_reference)
     }
 
-    pub fn player_mut(
-        &mut self,
-        player_reference: &PlayerReference,
-    ) -> miette::Result<&mut Player> {
-        match self.players.get_mut(player_reference.0) {
-            None => {
-                bail!(SelfishError::PlayerDoesNotExist(*player_reference,));
-            }
-            Some(player) => Ok(player),
-        }
+    /// The cards a hack suit could take from the target.
+    pub fn legal_cards_to_take(&self, target: &PlayerReference) -> Vec<GameCard> {
+        self.state.legal_cards_to_take(target)
     }
 
-    /// This subverts the borrow checker in a safe via a splitting borrow.
-    pub fn player_mut_rng(
-        &mut self,
-        player_reference: &PlayerReference,
-    ) -> miette::Result<(&mut Player, &mut ChaCha8Rng)> {
-        match self.players.get_mut(player_reference.0) {
-            None => {
-                bail!(SelfishError::PlayerDoesNotExist(*player_reference,));
-            }
-            Some(player) => Ok((player, &mut self.rng)),
+    /// Answer for the player, or take them out of the game if they have forfeited and it is
+    /// between actions.
+    fn impose(&mut self, player: PlayerReference) -> miette::Result<Option<PendingDecision>> {
+        if self.forfeiting[player.0] && self.state.can_forfeit() {
+            self.settle_forfeits()?;
+            return Ok(self.state.pending_decision().cloned());
         }
+
+        let (pending, decision) = match (
+            self.state.pending_decision().cloned(),
+            fallback_decision(&self.state),
+        ) {
+            (Some(pending), Some(decision)) => (pending, decision),
+            _ => bail!(SelfishError::NoDecisionPending),
+        };
+        self.emit_to(
+            &decision_audience(&pending),
+            GameEvent::DecisionImposed {
+                player,
+                decision: decision.clone(),
+            },
+        );
+        self.submit(decision)
     }
 
-    pub fn player(&self, player_reference: &PlayerReference) -> miette::Result<&Player> {
-        match self.players.get(player_reference.0) {
-            None => {
-                bail!(SelfishError::PlayerDoesNotExist(*player_reference));
+    /// Take out the players who have forfeited, once it is between actions.
+    fn settle_forfeits(&mut self) -> miette::Result<()> {
+        while self.state.can_forfeit() {
+            let Some(idx) = self.forfeiting.iter().position(|forfeiting| *forfeiting) else {
+                break;
+            };
+            self.forfeiting[idx] = false;
+            // They might have died in the meantime anyway.
+            if self.state.player(&PlayerReference(idx))?.alive {
+                self.state.forfeit(&PlayerReference(idx))?;
+                self.flush_events();
             }
-            Some(player) => Ok(player),
         }
+        Ok(())
     }
 
-    pub fn current_player(&mut self) -> &mut Player {
-        &mut self.players[self.whose_turn_reference.0]
-    }
+    /// Ask the controller of the seat for their answer to the pending decision.
+    fn ask(&mut self, pending: &PendingDecision) -> miette::Result<Decision> {
+        let player_reference = pending.player();
+
+        // Make sure the controller isn't deciding from an old view of the game, or from somebody
+        // else's.
+        let visible_state = VisibleState::try_from_state(&self.state, &player_reference)?;
+        self.controller(&player_reference)?
+            .update_state(visible_state);
 
-    pub fn current_controller(&mut self) -> miette::Result<&mut Box<dyn PlayerController>> {
-        let player_reference = self.whose_turn_reference;
-        self.controller(&player_reference)
+        Ok(self.controller(&player_reference)?.decide(pending))
     }
 
-    pub fn controller(
+    fn controller(
         &mut self,
         player_reference: &PlayerReference,
     ) -> miette::Result<&mut Box<dyn PlayerController>> {
         match self.controllers.get_mut(player_reference.0) {
-            None => {
-                bail!(SelfishError::PlayerDoesNotExist(*player_reference,));
+            Some(Some(controller)) => Ok(controller),
+            _ => {
+                bail!(SelfishError::NoController(*player_reference));
             }
-            Some(controller) => Ok(controller),
         }
     }
 
-    pub fn draw_card_phase(&mut self) -> GameCard {
-        assert_eq!(self.phase, Phase::Pickup);
-        let card = self.draw_card();
-        self.phase = Phase::Actions;
-        card
-    }
+    /// Pass on what happened in the state since the last step, then let observers look at the
+    /// result.
+    fn flush_events(&mut self) {
+        for (audience, event) in self.state.drain_events_with_audience() {
+            self.emit_to(&audience, event);
+        }
 
-    fn draw_card(&mut self) -> GameCard {
-        let card = self.game_deck.draw(&mut self.rng);
-        self.current_player().give(card);
-        card
+        let mut observers = std::mem::take(&mut self.observers);
+        for observer in &mut observers {
+            observer.on_step(self);
+        }
+        self.observers = observers;
     }
 
-    pub fn action(&mut self, action: Action) -> miette::Result<()> {
-        assert_eq!(self.phase, Phase::Actions);
-
-        let mut proceed = true;
-
-        let rules = action.rules();
-
-        if let Some(other_player_reference) = action.attacking() {
-            if other_player_reference == self.whose_turn_reference {
-                bail!(SelfishError::CantAttackYourself);
-            }
-
-            let other_player = self.player_mut(&other_player_reference)?;
-            if let Some(steal) = action.rules().steal {
-                if steal.count > other_player.hand.len() {
-                    bail!(SelfishError::PlayerDoesNotHaveEnoughCards(
-                        other_player_reference,
-                        steal.count
-                    ));
-                }
-            }
+    fn emit(&mut self, event: GameEvent) {
+        self.emit_to(&Audience::Everyone, event);
+    }
 
-            // Offer the other player a chance to shield.
-            if self.can_player_defend(&other_player_reference)?
-                && self.controller(&other_player_reference)?.defend(&action)
-            {
-                self.log(format!(
-                    "{:?} defended against {:?} with a shield.",
-                    other_player_reference, action
-                ));
-                self.player_mut(&other_player_reference)?
-                    .remove_card(&GameCard::Shield)
-                    .wrap_err("Controller requested to defend with shield.")?;
-                self.game_deck.add_to_discard(GameCard::Shield);
-                proceed = false;
+    fn emit_to(&mut self, audience: &Audience, event: GameEvent) {
+        // Observers are taken out while they run so that they can look at the game.
+        let mut observers = std::mem::take(&mut self.observers);
+        for observer in &mut observers {
+            if observer.seat().is_none_or(|seat| audience.includes(&seat)) {
+                observer.on_event(self, &event);
             }
         }
+        self.observers = observers;
+    }
+}
 
-        if proceed {
-            match action {
-                Action::OxygenSiphon { target } => {
-                    self.log(format!("Player will siphon oxygen from {:?}.", target));
-                    let target_player = self.player_mut(&target)?;
-                    match target_player.count_cards(&GameCard::O1) {
-                        0 => {
-                            self.player_died(
-                                &target,
-                                "was attacked by an oxygen siphon and didn't have enough oxygen",
-                            )?;
-                        }
-                        1 => {
-                            self.current_player().give(GameCard::O1);
-                            self.player_died(
-                                &target,
-                                "was attacked by an oxygen siphon and only had 1 oxygen",
-                            )?;
-                        }
-                        _ => {
-                            target_player.remove_card(&GameCard::O1)?;
-                            target_player.remove_card(&GameCard::O1)?;
-                            self.current_player().give(GameCard::O1);
-                        }
-                    }
-                }
-                Action::HackSuit { target } => {
-                    let target_player = self.player(&target)?;
-                    let possible_cards = target_player.unique_cards();
-                    let controller = self.current_controller()?;
-                    let card = controller.choose_card_to_take(possible_cards);
-                    let target_player = self.player_mut(&target)?;
-                    target_player.remove_card(&card)?;
-                    self.current_player().give(card);
-                    self.log(format!(
-                        "Player hacked {:?}'s suit and took a {:?}.",
-                        target, card
-                    ));
-                }
-                Action::TractorBeam { target } => {
-                    let random_card = self.remove_random_card(&target)?;
-                    self.current_player().give(random_card);
-                }
-                Action::RocketBooster => {
-                    self.add_space()?;
-                    self.log("Player used a rocket booster.".to_string());
-                }
-                Action::LaserBlast { target } => {
-                    let target_player = self.player_mut(&target)?;
-                    target_player.space.pop();
-                }
-                Action::HoleInSuit { .. } => {}
-                Action::Tether { .. } => {}
-            }
+/// Who may hear about an answer to the decision. Answers can give away cards, e.g. the card a hack
+/// suit takes, so only the players who would see the decision played are told.
+fn decision_audience(pending: &PendingDecision) -> Audience {
+    match pending {
+        PendingDecision::ChooseCardToTake { player, target, .. } => {
+            Audience::Only(vec![*target, *player])
         }
-
-        // Remove action card from the player's hand.
-
-        self.discard(&action.card())?;
-
-        Ok(())
+        _ => Audience::Only(vec![pending.player()]),
     }
+}
 
-    pub fn discard(&mut self, card: &GameCard) -> miette::Result<()> {
-        self.current_player()
-            .remove_card(card)
-            .wrap_err_with(|| format!("Discarding {:?}", &card))?;
-        self.game_deck.add_to_discard(GameCard::O1);
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RandomPlayerController, Recorder};
+    use rand::prelude::SliceRandom;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn random_controllers(seed: u64, players: usize) -> Vec<Box<dyn PlayerController>> {
+        (0..players as u64)
+            .map(|seat| {
+                Box::new(RandomPlayerController::with_seed(seed * 10 + seat))
+                    as Box<dyn PlayerController>
+            })
+            .collect()
     }
 
-    pub fn can_player_defend(&self, player_reference: &PlayerReference) -> miette::Result<bool> {
-        let player = self.player(player_reference)?;
-        let has_shield_card = player.hand.contains(&GameCard::Shield);
-        let in_solar_flare = player.in_solar_flare();
-        Ok(has_shield_card && !in_solar_flare)
+    fn new_game(seed: u64, players: usize) -> Game {
+        Game::new(Some(seed), random_controllers(seed, players))
     }
 
-    fn remove_random_card(
-        &mut self,
-        player_reference: &PlayerReference,
-    ) -> miette::Result<GameCard> {
-        let (other_player, rng) = self.player_mut_rng(player_reference)?;
-        other_player.remove_random_card(rng)
+    fn record_events(game: &mut Game) -> Rc<RefCell<Vec<GameEvent>>> {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let recorded = events.clone();
+        game.add_observer(Box::new(move |_: &Game, event: &GameEvent| {
+            recorded.borrow_mut().push(event.clone());
+        }));
+        events
     }
-}
-
-#[derive(Debug, PartialEq)]
-enum Phase {
-    Pickup,
-    Actions,
-    BreatheOrTravel,
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::RandomPlayerController;
-    use rand::{thread_rng, Rng};
+    #[test]
+    fn test_simulate_events() {
+        for seed in 0..5 {
+            let mut game = new_game(seed, 3);
+            let events = record_events(&mut game);
+            game.simulate().unwrap();
+            let events = events.borrow();
+            assert!(matches!(events[0], GameEvent::GameStarted { .. }));
+            let winner = game.winner();
+            assert!(
+                matches!(events.last(), Some(GameEvent::GameWon { player, .. }) if Some(*player) == winner),
+                "seed {}",
+                seed
+            );
+        }
+    }
 
-    fn new_game(players: usize) -> Game {
-        let seed = Some(thread_rng().gen());
-        println!("Seed: {:?}", seed);
+    #[test]
+    fn test_simulate_matches_state() {
+        // Feeding a game's decisions straight into a bare GameState plays out the same game.
+        for seed in 0..5 {
+            let recorder = Recorder::new();
+            let controllers = random_controllers(seed, 3);
+            let mut game = Game::new(Some(seed), recorder.wrap_all(controllers));
+            recorder.attach(&mut game);
+            game.simulate().unwrap();
+            let replay = recorder.finish(&game);
+
+            let mut state = GameState::new(Some(seed), 3);
+            let mut pending = state.start().unwrap();
+            let mut decisions = replay.decisions.into_iter();
+            while let Some(decision) = pending {
+                let recorded = decisions.next().unwrap();
+                assert_eq!(recorded.player, decision.player());
+                pending = state.apply(recorded.decision).unwrap();
+            }
+            assert_eq!(replay.events, state.drain_events(), "seed {}", seed);
+            assert_eq!(game.winner(), state.winner());
+        }
+    }
 
-        let mut controllers: Vec<Box<dyn PlayerController>> = Vec::new();
-        for _ in 0..players {
-            controllers.push(Box::new(RandomPlayerController::new()));
+    #[test]
+    fn test_submit_without_controllers() {
+        let mut game = Game::headless(Some(1), 3);
+        let events = record_events(&mut game);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut pending = game.start().unwrap();
+        while pending.is_some() {
+            assert!(game.step().is_err());
+            let decisions = game.state().legal_decisions();
+            let decision = decisions.choose(&mut rng).unwrap().clone();
+            pending = game.submit(decision).unwrap();
         }
-        Game::new(seed, controllers)
+        assert!(game.winner().is_some());
+        assert!(game.submit(Decision::PlayAction(None)).is_err());
+        assert!(matches!(
+            events.borrow().last(),
+            Some(GameEvent::GameWon { .. })
+        ));
     }
 
     #[test]
-    fn test_tractor_beam() {
-        let mut game = new_game(2);
-        // Cheat and put a tractor beam on the top of the deck.
-        game.game_deck.add_to_available(GameCard::TractorBeam);
-        game.draw_card_phase();
-        game.action(Action::TractorBeam {
-            target: PlayerReference(1),
-        })
-        .unwrap();
-        game.print();
-        assert_eq!(game.player(&PlayerReference(0)).unwrap().hand.len(), 6);
-        assert_eq!(game.player(&PlayerReference(1)).unwrap().hand.len(), 4);
+    fn test_invalid_submit_keeps_waiting() {
+        let mut game = Game::headless(Some(2), 2);
+        let pending = game.start().unwrap();
+        assert!(game.submit(Decision::Defend(true)).is_err());
+        assert!(game
+            .submit(Decision::PlayAction(Some(Action::LaserBlast {
+                target: PlayerReference(0)
+            })))
+            .is_err());
+        assert_eq!(game.pending_decision(), pending.as_ref());
+    }
+
+    #[test]
+    fn test_needs_two_players() {
+        for players in [0, 1] {
+            assert!(matches!(
+                Game::with_rules(Some(1), RuleSet::default(), random_controllers(1, players)),
+                Err(RuleError::NotEnoughPlayers(_))
+            ));
+        }
     }
 }
@@ -1,7 +1,9 @@
+use crate::DeckConfig;
 use rand::prelude::SliceRandom;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum GameCard {
     O1,
     O2,
@@ -15,57 +17,27 @@ pub enum GameCard {
     Tether,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GameDeck {
     available: Vec<GameCard>,
     discard: Vec<GameCard>,
 }
 
 impl GameDeck {
-    pub fn new() -> Self {
-        let mut available = Vec::new();
-        for _ in 0..38 {
-            available.push(GameCard::O1);
-        }
-        for _ in 0..10 {
-            available.push(GameCard::O2);
-        }
-        for _ in 0..10 {
-            available.push(GameCard::O2);
-        }
-        for _ in 0..3 {
-            available.push(GameCard::OxygenSiphon);
-        }
-        for _ in 0..4 {
-            available.push(GameCard::Shield);
-        }
-        for _ in 0..3 {
-            available.push(GameCard::HackSuit);
-        }
-        for _ in 0..4 {
-            available.push(GameCard::TractorBeam);
-        }
-        for _ in 0..4 {
-            available.push(GameCard::RocketBooster);
-        }
-        for _ in 0..4 {
-            available.push(GameCard::LaserBlast);
-        }
-        for _ in 0..4 {
-            available.push(GameCard::HoleInSuit);
-        }
-        for _ in 0..4 {
-            available.push(GameCard::Tether);
-        }
-
+    pub fn new(decks: &DeckConfig) -> Self {
         Self {
-            available,
+            available: decks.game_deck(),
             discard: Vec::new(),
         }
     }
 
-    pub fn shuffled(rng: &mut impl Rng) -> Self {
-        let mut deck = Self::new();
+    /// A deck part way through a game, with the next card to draw last.
+    pub(crate) fn from_parts(available: Vec<GameCard>, discard: Vec<GameCard>) -> Self {
+        Self { available, discard }
+    }
+
+    pub fn shuffled(decks: &DeckConfig, rng: &mut impl Rng) -> Self {
+        let mut deck = Self::new(decks);
         deck.shuffle(rng);
         deck
     }
@@ -81,22 +53,59 @@ impl GameDeck {
     }
 
     /// If there are no cards left, move the discard pile into the available pile and shuffle.
-    pub fn draw(&mut self, rng: &mut impl Rng) -> GameCard {
+    ///
+    /// Returns None when every card is in somebody's hand, which a small deck allows since hands
+    /// can grow without limit.
+    pub fn draw(&mut self, rng: &mut impl Rng) -> Option<GameCard> {
         if self.available.is_empty() {
             self.available.append(&mut self.discard);
             self.available.shuffle(rng);
-            self.discard.clear();
         }
-        self.available.pop().unwrap()
+        self.available.pop()
+    }
+
+    /// Remove every card that hasn't been drawn yet, e.g. to shuffle them with hidden hands.
+    pub fn take_available(&mut self) -> Vec<GameCard> {
+        std::mem::take(&mut self.available)
+    }
+
+    pub fn set_available(&mut self, available: Vec<GameCard>) {
+        self.available = available;
+    }
+
+    /// The cards left to draw, with the next one last.
+    pub fn available(&self) -> &[GameCard] {
+        &self.available
+    }
+
+    pub fn discard_pile(&self) -> &[GameCard] {
+        &self.discard
     }
 
     pub fn add_to_discard(&mut self, card: GameCard) {
         self.discard.push(card);
     }
 
-    // Used for cheating in tests!
-    #[cfg(test)]
-    pub fn add_to_available(&mut self, card: GameCard) {
-        self.available.push(card);
+    /// Take the latest discarded copy of the card back out. Returns false if there isn't one.
+    pub(crate) fn remove_from_discard(&mut self, card: &GameCard) -> bool {
+        match self.discard.iter().rposition(|c| c == card) {
+            None => false,
+            Some(idx) => {
+                self.discard.remove(idx);
+                true
+            }
+        }
+    }
+
+    /// Take the copy of the card nearest the top out of the cards left to draw. Returns false if
+    /// there isn't one.
+    pub(crate) fn remove_from_available(&mut self, card: &GameCard) -> bool {
+        match self.available.iter().rposition(|c| c == card) {
+            None => false,
+            Some(idx) => {
+                self.available.remove(idx);
+                true
+            }
+        }
     }
 }
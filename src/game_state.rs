@@ -0,0 +1,2060 @@
+use crate::actions::BreatheOrTravel;
+use crate::decision::{Decision, PendingDecision};
+use crate::errors::SelfishError;
+use crate::events::{Audience, DeathCause, GameEvent, WinReason};
+use crate::invariants::{InvariantError, Tally};
+use crate::oxygen::{oxygen_value, payments, take_change};
+use crate::scenario::Position;
+use crate::visible_state::VisibleState;
+use crate::{
+    Action, BeliefState, GameCard, GameDeck, Player, PlayerReference, RuleError, RuleSet,
+    SpaceCard, SpaceDeck,
+};
+use miette::{bail, WrapErr};
+use rand::prelude::SliceRandom;
+use rand::{thread_rng, Rng};
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// The complete rules state of a game, without anybody playing it.
+///
+/// The state moves from one `PendingDecision` to the next as decisions are applied, so it can be
+/// cloned to explore what would happen after any of them.
+#[derive(Clone)]
+pub struct GameState {
+    seed: u64,
+    rng: ChaCha8Rng,
+    winner: Option<PlayerReference>,
+    ship_distance: usize,
+    rules: Arc<RuleSet>,
+    game_deck: GameDeck,
+    space_deck: SpaceDeck,
+    players: Vec<Player>,
+    whose_turn_reference: PlayerReference,
+    phase: Phase,
+    pending: Option<PendingDecision>,
+    started: bool,
+    events: Vec<(Audience, GameEvent)>,
+    /// Every event so far, with who saw it, for the players' views.
+    history: Vec<(Audience, GameEvent)>,
+    /// What each player has learned from the events they saw.
+    beliefs: Vec<BeliefState>,
+    /// Whether every player holds the starting hand, rather than a scenario's.
+    freshly_dealt: bool,
+    record_events: bool,
+    checking_invariants: bool,
+}
+
+impl GameState {
+    /// Deals the starting hands with the official rules. Nothing happens until `start` is
+    /// called.
+    ///
+    /// Without a seed one is picked at random, which can still be read back with `seed`.
+    ///
+    /// Panics if the official rules can't be played with that many players, i.e. fewer than two
+    /// or more than the base decks can deal to. `with_rules` returns the error instead.
+    pub fn new(seed: Option<u64>, player_count: usize) -> GameState {
+        Self::with_rules(seed, player_count, RuleSet::default())
+            .expect("the official rules can be played with this many players")
+    }
+
+    /// Like `new`, but with a variant of the rules, e.g. loaded with `RuleSet::load`.
+    pub fn with_rules(
+        seed: Option<u64>,
+        player_count: usize,
+        rules: RuleSet,
+    ) -> Result<GameState, RuleError> {
+        rules.check(player_count)?;
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        let ship_distance = rules.ship_distance(player_count);
+        let space_deck = SpaceDeck::shuffled(&rules.decks, &mut rng);
+        let mut game_deck = GameDeck::shuffled(&rules.decks, &mut rng);
+        let mut players = Vec::new();
+        for _ in 0..player_count {
+            let mut player = Player::new();
+
+            for (card, count) in rules.starting_hand() {
+                for _ in 0..count {
+                    player.give(game_deck.take(card));
+                }
+            }
+
+            players.push(player);
+        }
+
+        Ok(GameState {
+            seed,
+            rng,
+            rules: Arc::new(rules),
+            game_deck,
+            space_deck,
+            players,
+            whose_turn_reference: PlayerReference(0),
+            phase: Phase::Pickup,
+            pending: None,
+            started: false,
+            winner: None,
+            ship_distance,
+            events: Vec::new(),
+            history: Vec::new(),
+            beliefs: fresh_beliefs(player_count),
+            freshly_dealt: true,
+            record_events: true,
+            checking_invariants: false,
+        })
+    }
+
+    /// Set up a game part way through, for `Scenario::build`.
+    pub(crate) fn from_position(position: Position) -> GameState {
+        let ship_distance = position.rules.ship_distance(position.players.len());
+        let beliefs = fresh_beliefs(position.players.len());
+        GameState {
+            seed: position.seed,
+            rng: position.rng,
+            rules: Arc::new(position.rules),
+            game_deck: position.game_deck,
+            space_deck: position.space_deck,
+            players: position.players,
+            whose_turn_reference: position.whose_turn,
+            phase: position.phase,
+            pending: None,
+            started: false,
+            winner: None,
+            ship_distance,
+            events: Vec::new(),
+            history: Vec::new(),
+            beliefs,
+            freshly_dealt: false,
+            record_events: true,
+            checking_invariants: false,
+        }
+    }
+
+    /// How many space cards a player needs in front of them to reach the ship.
+    ///
+    /// The ship is placed closer when there are more players, since each player gets fewer turns
+    /// to travel before somebody runs out of oxygen. `RuleSet::ship_distance` overrides it.
+    pub fn default_ship_distance(player_count: usize) -> usize {
+        match player_count {
+            0..=3 => 6,
+            4..=5 => 5,
+            _ => 4,
+        }
+    }
+
+    /// The seed that makes this game reproducible, given the same decisions.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Replace the random number generator, so that a copy of the state rolls differently from
+    /// the original from here on. `seed` still returns the original seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    /// Shuffle away everything the viewer can't know: the other players' hands and the order of
+    /// both decks. Hand sizes, space rows, the discard pile and cards the viewer knows somebody
+    /// holds stay as they are.
+    ///
+    /// Useful for search, where each copy of the state is one guess at the hidden information.
+    pub fn determinize(&mut self, viewer: &PlayerReference, rng: &mut impl Rng) {
+        let belief = match self.belief(viewer) {
+            Ok(belief) => belief.clone(),
+            Err(_) => BeliefState::new(*viewer, self.players.len()),
+        };
+        let mut hidden: Vec<GameCard> = self.game_deck.take_available();
+        let mut hand_sizes = Vec::new();
+        for (idx, player) in self.players.iter_mut().enumerate() {
+            hand_sizes.push(player.hand.len());
+            if idx != viewer.0 {
+                hidden.append(&mut player.hand);
+                for card in belief.known_cards(&PlayerReference(idx)) {
+                    if let Some(pos) = hidden.iter().rposition(|c| c == card) {
+                        player.hand.push(hidden.swap_remove(pos));
+                    }
+                }
+            }
+        }
+        hidden.shuffle(rng);
+
+        for (idx, player) in self.players.iter_mut().enumerate() {
+            if idx != viewer.0 {
+                let missing = hand_sizes[idx] - player.hand.len();
+                player.hand.extend(hidden.split_off(hidden.len() - missing));
+            }
+        }
+        self.game_deck.set_available(hidden);
+        self.space_deck.shuffle(rng);
+    }
+
+    /// Guess a full state from what a player can see, for the player to search from.
+    ///
+    /// The hidden hands are dealt at random from every card the viewer can't account for, and the
+    /// rest become the draw pile. The state waits on `pending`, which must be what the game asked
+    /// the viewer. Events are not recorded.
+    pub fn determinized(
+        visible_state: &VisibleState,
+        pending: PendingDecision,
+        rng: &mut impl Rng,
+    ) -> GameState {
+        let viewer = visible_state.viewer;
+
+        let mut unseen: Vec<GameCard> = visible_state
+            .unseen_cards()
+            .into_iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(card, count))
+            .collect();
+        unseen.shuffle(rng);
+
+        let mut players = Vec::new();
+        for (idx, visible_player) in visible_state.players.iter().enumerate() {
+            let hand = if idx == viewer.0 {
+                visible_state.my_hand.clone()
+            } else {
+                let mut hand = visible_player.known_cards.clone();
+                hand.truncate(visible_player.hand_size);
+                let missing = (visible_player.hand_size - hand.len()).min(unseen.len());
+                hand.extend(unseen.split_off(unseen.len() - missing));
+                hand
+            };
+            players.push(Player {
+                alive: visible_player.alive,
+                hand,
+                space: visible_player.space.clone(),
+            });
+        }
+        unseen.truncate(visible_state.deck_size);
+        let game_deck = GameDeck::from_parts(unseen, visible_state.discard_pile.clone());
+
+        let mut space_cards: Vec<SpaceCard> = visible_state
+            .space_deck
+            .iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(card.clone(), *count))
+            .collect();
+        space_cards.shuffle(rng);
+        let space_deck =
+            SpaceDeck::from_parts(space_cards, visible_state.space_discard_pile.clone());
+
+        GameState {
+            seed: rng.gen(),
+            rng: ChaCha8Rng::seed_from_u64(rng.gen()),
+            winner: None,
+            ship_distance: visible_state.ship_distance,
+            rules: visible_state.rules.clone(),
+            game_deck,
+            space_deck,
+            players,
+            whose_turn_reference: visible_state.whose_turn,
+            phase: visible_state.phase,
+            pending: Some(pending),
+            started: true,
+            events: Vec::new(),
+            history: Vec::new(),
+            beliefs: fresh_beliefs(visible_state.players.len()),
+            freshly_dealt: false,
+            record_events: false,
+            checking_invariants: false,
+        }
+    }
+
+    /// The rules the game is played by, including what the decks were made of before they were
+    /// shuffled.
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub(crate) fn shared_rules(&self) -> Arc<RuleSet> {
+        self.rules.clone()
+    }
+
+    pub fn ship_distance(&self) -> usize {
+        self.ship_distance
+    }
+
+    pub fn whose_turn(&self) -> PlayerReference {
+        self.whose_turn_reference
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn player(&self, player_reference: &PlayerReference) -> miette::Result<&Player> {
+        match self.players.get(player_reference.0) {
+            None => {
+                bail!(SelfishError::PlayerDoesNotExist(*player_reference));
+            }
+            Some(player) => Ok(player),
+        }
+    }
+
+    /// The player who reached the ship, or the last player alive.
+    pub fn winner(&self) -> Option<PlayerReference> {
+        self.winner
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Cards that have been played or thrown away since the deck was last shuffled.
+    pub fn discard_pile(&self) -> &[GameCard] {
+        self.game_deck.discard_pile()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    /// What the game is waiting on. None before `start` and once the game is over.
+    pub fn pending_decision(&self) -> Option<&PendingDecision> {
+        self.pending.as_ref()
+    }
+
+    /// Take the events that happened since the last call, including the ones only some players
+    /// saw.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        self.drain_events_with_audience()
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Take the events that happened since the last call, with who saw them.
+    pub(crate) fn drain_events_with_audience(&mut self) -> Vec<(Audience, GameEvent)> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Stop keeping events, e.g. for the throwaway copies made during a search. Players' views
+    /// of the state won't have a history either, and forget what they learned from it.
+    pub fn set_record_events(&mut self, record_events: bool) {
+        self.record_events = record_events;
+        if !record_events {
+            self.events.clear();
+            self.history.clear();
+            self.beliefs = fresh_beliefs(self.players.len());
+        }
+    }
+
+    /// What the player has learned about the other hands so far.
+    pub fn belief(&self, viewer: &PlayerReference) -> miette::Result<&BeliefState> {
+        match self.beliefs.get(viewer.0) {
+            None => {
+                bail!(SelfishError::PlayerDoesNotExist(*viewer));
+            }
+            Some(belief) => Ok(belief),
+        }
+    }
+
+    /// Every event the player saw, oldest first.
+    pub fn history(&self, viewer: &PlayerReference) -> Vec<GameEvent> {
+        self.history
+            .iter()
+            .filter(|(audience, _)| audience.includes(viewer))
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    /// The space cards left to draw, in no particular order, and the ones knocked out of rows
+    /// since it was last shuffled.
+    pub fn space_deck(&self) -> (&[SpaceCard], &[SpaceCard]) {
+        (self.space_deck.cards(), self.space_deck.discard_pile())
+    }
+
+    /// How many cards are left to draw.
+    pub fn deck_size(&self) -> usize {
+        self.game_deck.available().len()
+    }
+
+    /// The cards left to draw, with the next one last.
+    #[cfg(any(test, feature = "fuzz"))]
+    pub(crate) fn deck(&self) -> &[GameCard] {
+        self.game_deck.available()
+    }
+
+    #[cfg(any(test, feature = "fuzz"))]
+    pub(crate) fn rng(&self) -> &ChaCha8Rng {
+        &self.rng
+    }
+
+    /// Check the invariants after every step from now on, failing with an `InvariantError` as
+    /// soon as one breaks. Off by default, since it counts every card after every decision.
+    pub fn set_check_invariants(&mut self, checking_invariants: bool) {
+        self.checking_invariants = checking_invariants;
+    }
+
+    /// Everything that must hold between steps, whatever the players do:
+    ///
+    /// * Every card in the decks is in a hand, space row, deck or discard pile, exactly once.
+    /// * Dead players hold no cards.
+    /// * The pending decision fits the phase and whose turn it is.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.check_card_counts()?;
+
+        for (idx, player) in self.players.iter().enumerate() {
+            if !player.alive && !player.hand.is_empty() {
+                return Err(InvariantError::DeadPlayerHoldsCards {
+                    player: PlayerReference(idx),
+                    cards: player.hand.clone(),
+                });
+            }
+        }
+
+        if self.started {
+            self.check_pending()?;
+        }
+        Ok(())
+    }
+
+    fn check_card_counts(&self) -> Result<(), InvariantError> {
+        let decks = &self.rules.decks;
+
+        let mut game_cards: BTreeMap<GameCard, Tally> = BTreeMap::new();
+        for card in self.players.iter().flat_map(|player| &player.hand) {
+            game_cards.entry(*card).or_default().held += 1;
+        }
+        for card in self.game_deck.available() {
+            game_cards.entry(*card).or_default().in_deck += 1;
+        }
+        for card in self.game_deck.discard_pile() {
+            game_cards.entry(*card).or_default().discarded += 1;
+        }
+        for card in decks.game_cards.keys() {
+            game_cards.entry(*card).or_default();
+        }
+        for (card, tally) in game_cards {
+            let expected = decks.game_card_count(card);
+            if tally.total() != expected {
+                return Err(InvariantError::GameCardCount {
+                    card,
+                    expected,
+                    tally,
+                });
+            }
+        }
+
+        let mut space_cards: BTreeMap<SpaceCard, Tally> = BTreeMap::new();
+        for card in self.players.iter().flat_map(|player| &player.space) {
+            space_cards.entry(card.clone()).or_default().held += 1;
+        }
+        for card in self.space_deck.cards() {
+            space_cards.entry(card.clone()).or_default().in_deck += 1;
+        }
+        for card in self.space_deck.discard_pile() {
+            space_cards.entry(card.clone()).or_default().discarded += 1;
+        }
+        for card in decks.space_cards.keys() {
+            space_cards.entry(card.clone()).or_default();
+        }
+        for (card, tally) in space_cards {
+            let expected = decks.space_cards.get(&card).copied().unwrap_or_default();
+            if tally.total() != expected {
+                return Err(InvariantError::SpaceCardCount {
+                    card,
+                    expected,
+                    tally,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_pending(&self) -> Result<(), InvariantError> {
+        let pending = match (&self.pending, self.is_game_over()) {
+            (Some(pending), true) => {
+                return Err(InvariantError::PendingAfterGameOver(pending.kind()));
+            }
+            (None, true) => return Ok(()),
+            (None, false) => return Err(InvariantError::NothingPending),
+            (Some(pending), false) => pending,
+        };
+
+        let whose_turn = self.whose_turn_reference;
+        if !self.players[whose_turn.0].alive {
+            return Err(InvariantError::DeadPlayersTurn(whose_turn));
+        }
+        let player = pending.player();
+        if !self.players.get(player.0).is_some_and(|p| p.alive) {
+            return Err(InvariantError::DeadPlayerDeciding(player));
+        }
+
+        let (phases, defending): (&[Phase], bool) = match pending {
+            PendingDecision::PlayAction { .. } | PendingDecision::ChooseCardToTake { .. } => {
+                (&[Phase::Actions], false)
+            }
+            PendingDecision::Defend { .. } => (&[Phase::Actions], true),
+            PendingDecision::BreatheOrTravel { .. } => (&[Phase::BreatheOrTravel], false),
+            // Space cards are revealed by travelling, or by a rocket booster during actions.
+            PendingDecision::ForcedDiscard { .. } | PendingDecision::ChooseSwapTarget { .. } => {
+                (&[Phase::Actions, Phase::BreatheOrTravel], false)
+            }
+            // Attacks are paid for by their target, and space cards by whoever revealed them.
+            PendingDecision::PayOxygen { cause, .. } => match cause {
+                DeathCause::OxygenSiphon | DeathCause::HoleInSuit => (&[Phase::Actions], true),
+                _ => (&[Phase::Actions, Phase::BreatheOrTravel], false),
+            },
+        };
+        if !phases.contains(&self.phase) {
+            return Err(InvariantError::WrongPhase {
+                pending: pending.kind(),
+                phase: self.phase,
+            });
+        }
+        if (player == whose_turn) == defending {
+            return Err(InvariantError::WrongPlayer {
+                pending: pending.kind(),
+                player,
+                whose_turn,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// What the player can see.
+    pub fn visible_state(&self, viewer: &PlayerReference) -> miette::Result<VisibleState> {
+        VisibleState::try_from_state(self, viewer)
+    }
+
+    /// The viewer's view of the table, for checking their decisions without copying everything
+    /// they have seen.
+    fn rules_view(&self, viewer: &PlayerReference) -> miette::Result<VisibleState> {
+        VisibleState::for_rules(self, viewer)
+    }
+
+    /// Begin the first turn, returning the first decision.
+    pub fn start(&mut self) -> miette::Result<Option<PendingDecision>> {
+        if !self.started {
+            self.started = true;
+            let dealt = if self.freshly_dealt {
+                self.rules
+                    .starting_hand()
+                    .into_iter()
+                    .flat_map(|(card, count)| std::iter::repeat_n(card, count))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            self.emit(GameEvent::GameStarted {
+                player_count: self.player_count(),
+                ship_distance: self.ship_distance,
+                dealt,
+            });
+            // A scenario can start part way through a turn.
+            if self.phase != Phase::Pickup {
+                self.emit(GameEvent::TurnStarted {
+                    player: self.whose_turn_reference,
+                });
+                match self.phase {
+                    Phase::Actions => self.continue_actions()?,
+                    _ => self.breathe_or_travel_phase()?,
+                }
+            }
+            self.run()?;
+            if self.checking_invariants {
+                self.check_invariants()?;
+            }
+        }
+        Ok(self.pending.clone())
+    }
+
+    /// Take the player out of the game as if they had died, e.g. when their controller keeps
+    /// breaking the rules.
+    ///
+    /// Only possible between actions, see `can_forfeit`, so that nothing half played still
+    /// depends on the player.
+    pub fn forfeit(
+        &mut self,
+        player_reference: &PlayerReference,
+    ) -> miette::Result<Option<PendingDecision>> {
+        if !self.can_forfeit() {
+            bail!(SelfishError::CantForfeitNow);
+        }
+        if !self.player(player_reference)?.alive {
+            bail!(SelfishError::PlayerIsDead(*player_reference));
+        }
+
+        self.player_died(player_reference, DeathCause::Forfeited);
+        if *player_reference == self.whose_turn_reference && !self.is_game_over() {
+            self.pending = None;
+            self.end_turn();
+        }
+        self.run()?;
+        if self.checking_invariants {
+            self.check_invariants()?;
+        }
+        Ok(self.pending.clone())
+    }
+
+    /// Whether the game is between actions, waiting for the current player to play one or to
+    /// breathe or travel.
+    pub fn can_forfeit(&self) -> bool {
+        matches!(
+            self.pending,
+            Some(PendingDecision::PlayAction { .. } | PendingDecision::BreatheOrTravel { .. })
+        )
+    }
+
+    /// Answer the pending decision and run the game until the next one.
+    ///
+    /// An invalid decision is an error and leaves the state untouched.
+    pub fn apply(&mut self, decision: Decision) -> miette::Result<Option<PendingDecision>> {
+        let pending = match &self.pending {
+            None => bail!(SelfishError::NoDecisionPending),
+            Some(pending) => pending.clone(),
+        };
+
+        match (pending, decision) {
+            (PendingDecision::PlayAction { .. }, Decision::PlayAction(None)) => {
+                self.pending = None;
+                self.breathe_or_travel_phase()?;
+            }
+            (PendingDecision::PlayAction { player }, Decision::PlayAction(Some(action))) => {
+                self.rules_view(&player)?.check_action(&action)?;
+                self.pending = None;
+                self.play_action(action)?;
+            }
+            (PendingDecision::Defend { player, action }, Decision::Defend(defend)) => {
+                if defend && !self.can_player_defend(&player)? {
+                    bail!(SelfishError::PlayerDoesNotHaveThisCard(GameCard::Shield));
+                }
+                self.pending = None;
+                if defend {
+                    self.player_mut(&player)?
+                        .remove_card(&GameCard::Shield)
+                        .wrap_err("Defending with a shield.")?;
+                    self.game_deck.add_to_discard(GameCard::Shield);
+                    self.emit(GameEvent::ShieldUsed { player, action });
+                    self.continue_actions()?;
+                } else {
+                    self.resolve_action(action)?;
+                }
+            }
+            (
+                PendingDecision::ChooseCardToTake {
+                    player,
+                    target,
+                    options,
+                },
+                Decision::TakeCard(card),
+            ) => {
+                if !options.contains(&card) {
+                    bail!(SelfishError::TargetDoesNotHaveThisCard(target, card));
+                }
+                self.pending = None;
+                self.player_mut(&target)?.remove_card(&card)?;
+                self.player_mut(&player)?.give(card);
+                self.emit_to(
+                    Audience::Only(vec![target, player]),
+                    GameEvent::CardStolen {
+                        from: target,
+                        to: player,
+                        card,
+                    },
+                );
+                self.continue_actions()?;
+            }
+            (PendingDecision::BreatheOrTravel { player }, Decision::BreatheOrTravel(choice)) => {
+                if !self
+                    .rules_view(&player)?
+                    .legal_breathe_or_travel()
+                    .contains(&choice)
+                {
+                    let card = match choice {
+                        BreatheOrTravel::Breathe => GameCard::O1,
+                        BreatheOrTravel::Travel => GameCard::O2,
+                    };
+                    bail!(SelfishError::PlayerDoesNotHaveThisCard(card));
+                }
+                self.pending = None;
+                self.breathe_or_travel(choice)?;
+            }
+            (
+                PendingDecision::ForcedDiscard { player, card_count },
+                Decision::ForcedDiscard(cards),
+            ) => {
+                self.rules_view(&player)?
+                    .check_discard(&cards, card_count)
+                    .wrap_err("Meteoroid.")?;
+                self.pending = None;
+                for card in &cards {
+                    self.player_mut(&player)?.remove_card(card)?;
+                    self.game_deck.add_to_discard(*card);
+                }
+                self.emit(GameEvent::CardsDiscarded { player, cards });
+                self.space_resolved()?;
+            }
+            (
+                PendingDecision::PayOxygen {
+                    player,
+                    cost,
+                    cause,
+                    options,
+                },
+                Decision::PayOxygen(cards),
+            ) => {
+                let mut sorted = cards.clone();
+                sorted.sort();
+                if !options.contains(&sorted) {
+                    bail!(SelfishError::InvalidPayment { cost, cards });
+                }
+                self.pending = None;
+                self.pay_oxygen(&player, sorted, cost, cause);
+                match cause {
+                    DeathCause::OxygenSiphon | DeathCause::HoleInSuit => self.continue_actions()?,
+                    _ => self.space_resolved()?,
+                }
+            }
+            (PendingDecision::ChooseSwapTarget { player }, Decision::SwapWith(target)) => {
+                self.rules_view(&player)?
+                    .check_swap_target(&target)
+                    .wrap_err("Wormhole.")?;
+                self.pending = None;
+                self.swap_space(&player, &target)?;
+                self.emit(GameEvent::SpacesSwapped {
+                    player,
+                    with: target,
+                });
+                self.space_resolved()?;
+            }
+            (pending, decision) => {
+                bail!(SelfishError::WrongDecision {
+                    expected: pending.kind(),
+                    actual: decision.kind(),
+                });
+            }
+        }
+
+        self.run()?;
+        if self.checking_invariants {
+            self.check_invariants()?;
+        }
+        Ok(self.pending.clone())
+    }
+
+    /// Every action the player could play right now. Empty if it isn't their action phase.
+    pub fn legal_actions(&self, player_reference: &PlayerReference) -> Vec<Action> {
+        match self.rules_view_if_pending(player_reference, "play_action") {
+            Some(visible_state) => visible_state.legal_actions(),
+            None => Vec::new(),
+        }
+    }
+
+    /// What the player can do at the end of their turn. Empty if it isn't their turn.
+    pub fn legal_breathe_or_travel(
+        &self,
+        player_reference: &PlayerReference,
+    ) -> Vec<BreatheOrTravel> {
+        if *player_reference != self.whose_turn_reference || self.is_game_over() {
+            return Vec::new();
+        }
+        match self.rules_view(player_reference) {
+            Ok(visible_state) => visible_state.legal_breathe_or_travel(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Who the player can swap with when they are on a wormhole.
+    pub fn legal_swap_targets(&self, player_reference: &PlayerReference) -> Vec<PlayerReference> {
+        match self.rules_view_if_pending(player_reference, "choose_player_to_swap_with") {
+            Some(visible_state) => visible_state.legal_swap_targets(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The ways the player can discard when a meteoroid has hit them.
+    pub fn legal_discards(&self, player_reference: &PlayerReference) -> Vec<Vec<GameCard>> {
+        let card_count = match &self.pending {
+            Some(PendingDecision::ForcedDiscard { card_count, .. }) => *card_count,
+            _ => return Vec::new(),
+        };
+        match self.rules_view_if_pending(player_reference, "forced_discard") {
+            Some(visible_state) => visible_state.legal_discards(card_count),
+            None => Vec::new(),
+        }
+    }
+
+    /// The cards a hack suit could take from the target.
+    pub fn legal_cards_to_take(&self, target: &PlayerReference) -> Vec<GameCard> {
+        let mut cards: Vec<GameCard> = match self.player(target) {
+            Ok(player) => player.unique_cards().into_iter().collect(),
+            Err(_) => Vec::new(),
+        };
+        cards.sort_by_key(|card| *card as usize);
+        cards
+    }
+
+    /// Every decision that `apply` would accept right now.
+    pub fn legal_decisions(&self) -> Vec<Decision> {
+        let pending = match &self.pending {
+            None => return Vec::new(),
+            Some(pending) => pending,
+        };
+        let player = pending.player();
+        match pending {
+            PendingDecision::PlayAction { .. } => {
+                let mut decisions = vec![Decision::PlayAction(None)];
+                decisions.extend(
+                    self.legal_actions(&player)
+                        .into_iter()
+                        .map(|action| Decision::PlayAction(Some(action))),
+                );
+                decisions
+            }
+            PendingDecision::Defend { .. } => vec![Decision::Defend(true), Decision::Defend(false)],
+            PendingDecision::ChooseCardToTake { options, .. } => options
+                .iter()
+                .map(|card| Decision::TakeCard(*card))
+                .collect(),
+            PendingDecision::BreatheOrTravel { .. } => self
+                .legal_breathe_or_travel(&player)
+                .into_iter()
+                .map(Decision::BreatheOrTravel)
+                .collect(),
+            PendingDecision::ForcedDiscard { .. } => self
+                .legal_discards(&player)
+                .into_iter()
+                .map(Decision::ForcedDiscard)
+                .collect(),
+            PendingDecision::ChooseSwapTarget { .. } => self
+                .legal_swap_targets(&player)
+                .into_iter()
+                .map(Decision::SwapWith)
+                .collect(),
+            PendingDecision::PayOxygen { options, .. } => options
+                .iter()
+                .map(|cards| Decision::PayOxygen(cards.clone()))
+                .collect(),
+        }
+    }
+
+    fn rules_view_if_pending(
+        &self,
+        player_reference: &PlayerReference,
+        kind: &str,
+    ) -> Option<VisibleState> {
+        match &self.pending {
+            Some(pending) if pending.kind() == kind && pending.player() == *player_reference => {
+                self.rules_view(player_reference).ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        self.emit_to(Audience::Everyone, event);
+    }
+
+    fn emit_to(&mut self, audience: Audience, event: GameEvent) {
+        if self.record_events {
+            for belief in &mut self.beliefs {
+                if audience.includes(&belief.viewer()) {
+                    belief.observe(&event);
+                }
+            }
+            self.history.push((audience.clone(), event.clone()));
+            self.events.push((audience, event));
+        }
+    }
+
+    fn next_player(&mut self) {
+        let next_player = (self.whose_turn_reference.0 + 1) % self.players.len();
+        self.whose_turn_reference = PlayerReference(next_player);
+    }
+
+    /// Play turns until somebody has to make a decision or the game is over.
+    fn run(&mut self) -> miette::Result<()> {
+        while self.pending.is_none() && !self.is_game_over() {
+            self.begin_turn()?;
+        }
+        Ok(())
+    }
+
+    /// Skip to the next living player, pick up a card and start asking for actions.
+    fn begin_turn(&mut self) -> miette::Result<()> {
+        while !self.current_player().alive {
+            self.next_player();
+        }
+
+        self.emit(GameEvent::TurnStarted {
+            player: self.whose_turn_reference,
+        });
+
+        // Always start at the pickup phase.
+        self.phase = Phase::Pickup;
+        self.draw_card();
+        self.phase = Phase::Actions;
+
+        self.continue_actions()
+    }
+
+    /// `run` starts the next turn once nothing is pending.
+    fn end_turn(&mut self) {
+        self.phase = Phase::Pickup;
+        self.next_player();
+    }
+
+    /// Keep asking the player for an action until they don't want to do any more.
+    fn continue_actions(&mut self) -> miette::Result<()> {
+        if self.is_game_over() {
+            return Ok(());
+        }
+
+        let player = self.current_player();
+        if !player.alive {
+            // The player died during their own turn, e.g. from a rocket booster.
+            self.end_turn();
+        } else if player.in_solar_flare() && !self.rules.house_rules.actions_in_solar_flare {
+            self.breathe_or_travel_phase()?;
+        } else {
+            self.pending = Some(PendingDecision::PlayAction {
+                player: self.whose_turn_reference,
+            });
+        }
+        Ok(())
+    }
+
+    /// If the player has no oxygen cards, they die.
+    /// If the player only has either an O1 or an O2, they are automatically played without
+    /// asking the controller.
+    /// Otherwise, ask the controller to play a O1 or a O2.
+    fn breathe_or_travel_phase(&mut self) -> miette::Result<()> {
+        self.phase = Phase::BreatheOrTravel;
+
+        let player = self.current_player();
+        let has_o1 = player.has_card(&GameCard::O1);
+        let has_o2 = player.has_card(&GameCard::O2);
+
+        match (has_o1, has_o2) {
+            (false, false) => {
+                let whose_turn_reference = self.whose_turn_reference;
+                self.player_died(&whose_turn_reference, DeathCause::NoOxygen);
+                self.end_turn();
+                Ok(())
+            }
+            (true, false) => self.breathe_or_travel(BreatheOrTravel::Breathe),
+            (false, true) => self.breathe_or_travel(BreatheOrTravel::Travel),
+            (true, true) => {
+                // The player has both an O1 and an O2, so ask the controller to play one.
+                self.pending = Some(PendingDecision::BreatheOrTravel {
+                    player: self.whose_turn_reference,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn breathe_or_travel(&mut self, choice: BreatheOrTravel) -> miette::Result<()> {
+        let whose_turn_reference = self.whose_turn_reference;
+        let player = self.current_player();
+        match choice {
+            BreatheOrTravel::Breathe => {
+                player.remove_card(&GameCard::O1).wrap_err("Breathing.")?;
+                self.game_deck.add_to_discard(GameCard::O1);
+                self.emit(GameEvent::Breathed {
+                    player: whose_turn_reference,
+                });
+                self.end_turn();
+            }
+            BreatheOrTravel::Travel => {
+                player.remove_card(&GameCard::O2).wrap_err("Travelling.")?;
+                self.game_deck.add_to_discard(GameCard::O2);
+                self.emit(GameEvent::Travelled {
+                    player: whose_turn_reference,
+                });
+                self.add_space();
+                if self.pending.is_none() {
+                    self.space_resolved()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A space card has been dealt with, so carry on with whatever revealed it.
+    fn space_resolved(&mut self) -> miette::Result<()> {
+        match self.phase {
+            Phase::Actions => self.continue_actions(),
+            _ => {
+                self.end_turn();
+                Ok(())
+            }
+        }
+    }
+
+    /// The player's hand goes to the discard pile, and their space row stays where it is.
+    fn player_died(&mut self, player_reference: &PlayerReference, cause: DeathCause) {
+        let player = &mut self.players[player_reference.0];
+        player.alive = false;
+        for card in player.hand.drain(..) {
+            self.game_deck.add_to_discard(card);
+        }
+        self.emit(GameEvent::PlayerDied {
+            player: *player_reference,
+            cause,
+        });
+
+        self.check_game_over();
+    }
+
+    fn check_game_over(&mut self) {
+        let mut alive_count = 0;
+        for player in &self.players {
+            if player.alive {
+                alive_count += 1;
+            }
+        }
+
+        if alive_count == 1 {
+            self.winner = self
+                .players
+                .iter()
+                .position(|player| player.alive)
+                .map(PlayerReference);
+            self.pending = None;
+            if let Some(player) = self.winner {
+                self.emit(GameEvent::GameWon {
+                    player,
+                    reason: WinReason::LastSurvivor,
+                });
+            }
+        }
+    }
+
+    /// Ends the game if the player's space row has reached the ship.
+    ///
+    /// Returns true if the player won.
+    fn check_reached_ship(&mut self, player_reference: &PlayerReference) -> bool {
+        if self.is_game_over() || self.players[player_reference.0].space.len() < self.ship_distance
+        {
+            return false;
+        }
+
+        self.winner = Some(*player_reference);
+        self.pending = None;
+        self.emit(GameEvent::GameWon {
+            player: *player_reference,
+            reason: WinReason::ReachedShip,
+        });
+
+        true
+    }
+
+    /// Reveal a space card for the current player and resolve it.
+    ///
+    /// If the card needs a decision it is left pending, and `space_resolved` carries on once it
+    /// has been answered.
+    fn add_space(&mut self) {
+        let whose_turn_reference = self.whose_turn_reference;
+        let space_card = self
+            .space_deck
+            .draw(&mut self.rng)
+            .expect("RuleSet::check makes sure there are always space cards to draw");
+
+        let player = self.current_player();
+        player.space.push(space_card.clone());
+        self.emit(GameEvent::SpaceCardRevealed {
+            player: whose_turn_reference,
+            card: space_card.clone(),
+        });
+
+        // Arriving at the ship wins straight away, before the space card takes effect. This breaks
+        // the ties that the physical rules leave open: a Hyperspace jump landing on the ship does
+        // not draw its extra card, and a Rocket Booster or travel onto a hazard never resolves it.
+        if self.check_reached_ship(&whose_turn_reference) {
+            return;
+        }
+
+        match &space_card {
+            SpaceCard::BlankSpace => {}
+            SpaceCard::UsefulJunk => {
+                self.draw_card();
+            }
+            SpaceCard::MysteriousNebula => {
+                self.draw_card();
+                self.draw_card();
+            }
+            SpaceCard::Hyperspace => {
+                self.add_space();
+            }
+            SpaceCard::Meteoroid => {
+                if self.current_player().hand.len() > self.rules.meteoroid_hand_limit {
+                    self.pending = Some(PendingDecision::ForcedDiscard {
+                        player: whose_turn_reference,
+                        card_count: self.rules.meteoroid_discard,
+                    });
+                }
+            }
+            SpaceCard::CosmicRadiation => {
+                self.charge_oxygen(&whose_turn_reference, 1, DeathCause::CosmicRadiation);
+            }
+            SpaceCard::AsteroidField => {
+                let damage = self.rules.asteroid_field_damage;
+                self.charge_oxygen(&whose_turn_reference, damage, DeathCause::AsteroidField);
+            }
+            SpaceCard::GravitationalAnomaly => {
+                // The player is pulled back a space, so both the anomaly and the card before it
+                // are lost, leaving them behind where they started.
+                for _ in 0..2 {
+                    if let Some(card) = self.current_player().space.pop() {
+                        self.space_deck.add_to_discard(card.clone());
+                        self.emit(GameEvent::SpaceCardLost {
+                            player: whose_turn_reference,
+                            card,
+                        });
+                    }
+                }
+            }
+            SpaceCard::WormHole => {
+                self.pending = Some(PendingDecision::ChooseSwapTarget {
+                    player: whose_turn_reference,
+                });
+            }
+            SpaceCard::SolarFlare => {
+                // Nothing happens except that they can't use action cards, unless a house rule
+                // says otherwise.
+            }
+        }
+    }
+
+    fn swap_space(&mut self, p1: &PlayerReference, p2: &PlayerReference) -> miette::Result<()> {
+        let p1_space = self.player(p1)?.space.clone();
+        let p2_space = self.player(p2)?.space.clone();
+
+        self.player_mut(p1)?.space = p2_space;
+        self.player_mut(p2)?.space = p1_space;
+
+        Ok(())
+    }
+
+    /// The player must pay the oxygen to survive, otherwise they die of the cause.
+    ///
+    /// If there is more than one way to pay, the player is asked how and `pay_oxygen` carries on
+    /// once they have answered. Otherwise they pay straight away.
+    fn charge_oxygen(
+        &mut self,
+        player_reference: &PlayerReference,
+        cost: usize,
+        cause: DeathCause,
+    ) {
+        let player = &self.players[player_reference.0];
+        if !player.alive || cost == 0 {
+            return;
+        }
+
+        let mut options = payments(&player.hand, cost);
+        match options.len() {
+            0 => {
+                // Not enough to survive, so everything goes.
+                let cards: Vec<GameCard> = player
+                    .hand
+                    .iter()
+                    .filter(|card| oxygen_value(card) > 0)
+                    .copied()
+                    .collect();
+                self.pay_oxygen(player_reference, cards, cost, cause);
+            }
+            1 => {
+                let cards = options.remove(0);
+                self.pay_oxygen(player_reference, cards, cost, cause);
+            }
+            _ => {
+                self.pending = Some(PendingDecision::PayOxygen {
+                    player: *player_reference,
+                    cost,
+                    cause,
+                    options,
+                });
+            }
+        }
+    }
+
+    /// An oxygen siphon's cost goes to the player whose turn it is, and everything else is
+    /// discarded. Paying too much is given back in O1s, and paying too little is fatal.
+    ///
+    /// An O2 that is worth more than a siphon still owes is discarded too, and broken into O1s
+    /// for the siphoning player and the change.
+    fn pay_oxygen(
+        &mut self,
+        player_reference: &PlayerReference,
+        cards: Vec<GameCard>,
+        cost: usize,
+        cause: DeathCause,
+    ) {
+        let whose_turn_reference = self.whose_turn_reference;
+        let siphon = cause == DeathCause::OxygenSiphon;
+        let mut paid = 0;
+        let mut siphoned = 0;
+        let mut discarded = Vec::new();
+        for card in &cards {
+            if self.players[player_reference.0].remove_card(card).is_err() {
+                continue;
+            }
+            paid += oxygen_value(card);
+            if siphon && siphoned + oxygen_value(card) <= cost {
+                siphoned += oxygen_value(card);
+                self.current_player().give(*card);
+                self.emit(GameEvent::CardStolen {
+                    from: *player_reference,
+                    to: whose_turn_reference,
+                    card: *card,
+                });
+            } else {
+                self.game_deck.add_to_discard(*card);
+                discarded.push(*card);
+            }
+        }
+        if !discarded.is_empty() {
+            self.emit(GameEvent::CardsDiscarded {
+                player: *player_reference,
+                cards: discarded,
+            });
+        }
+
+        if paid < cost {
+            self.player_died(player_reference, cause);
+            return;
+        }
+        if siphon && siphoned < cost {
+            let owed = take_change(
+                &mut self.game_deck,
+                cost - siphoned,
+                self.rules.oxygen_change,
+            );
+            if !owed.is_empty() {
+                for card in &owed {
+                    self.current_player().give(*card);
+                }
+                self.emit(GameEvent::ChangeGiven {
+                    player: whose_turn_reference,
+                    cards: owed,
+                });
+            }
+        }
+        let change = take_change(&mut self.game_deck, paid - cost, self.rules.oxygen_change);
+        if !change.is_empty() {
+            for card in &change {
+                self.players[player_reference.0].give(*card);
+            }
+            self.emit(GameEvent::ChangeGiven {
+                player: *player_reference,
+                cards: change,
+            });
+        }
+    }
+
+    fn player_mut(&mut self, player_reference: &PlayerReference) -> miette::Result<&mut Player> {
+        match self.players.get_mut(player_reference.0) {
+            None => {
+                bail!(SelfishError::PlayerDoesNotExist(*player_reference,));
+            }
+            Some(player) => Ok(player),
+        }
+    }
+
+    /// This subverts the borrow checker in a safe via a splitting borrow.
+    fn player_mut_rng(
+        &mut self,
+        player_reference: &PlayerReference,
+    ) -> miette::Result<(&mut Player, &mut ChaCha8Rng)> {
+        match self.players.get_mut(player_reference.0) {
+            None => {
+                bail!(SelfishError::PlayerDoesNotExist(*player_reference,));
+            }
+            Some(player) => Ok((player, &mut self.rng)),
+        }
+    }
+
+    fn current_player(&mut self) -> &mut Player {
+        &mut self.players[self.whose_turn_reference.0]
+    }
+
+    /// Nothing is drawn if every card is already in somebody's hand.
+    fn draw_card(&mut self) {
+        let Some(card) = self.game_deck.draw(&mut self.rng) else {
+            return;
+        };
+        self.current_player().give(card);
+        self.emit_to(
+            Audience::Only(vec![self.whose_turn_reference]),
+            GameEvent::CardDrawn {
+                player: self.whose_turn_reference,
+                card,
+            },
+        );
+    }
+
+    /// The action has already been checked. The card is played straight away, and the target gets
+    /// to shield if they can.
+    fn play_action(&mut self, action: Action) -> miette::Result<()> {
+        self.discard(&action.card())?;
+
+        self.emit(GameEvent::ActionPlayed {
+            player: self.whose_turn_reference,
+            action: action.clone(),
+        });
+
+        if let Some(other_player_reference) = action.attacking() {
+            // Offer the other player a chance to shield.
+            if self.can_player_defend(&other_player_reference)? {
+                self.pending = Some(PendingDecision::Defend {
+                    player: other_player_reference,
+                    action,
+                });
+                return Ok(());
+            }
+        }
+
+        self.resolve_action(action)
+    }
+
+    fn resolve_action(&mut self, action: Action) -> miette::Result<()> {
+        let whose_turn_reference = self.whose_turn_reference;
+        match action {
+            Action::OxygenSiphon { target } => {
+                // The target pays the oxygen to the player. Running out kills them.
+                let count = self.rules.oxygen_siphon_count;
+                self.charge_oxygen(&target, count, DeathCause::OxygenSiphon);
+                if self.pending.is_some() {
+                    return Ok(());
+                }
+            }
+            Action::HackSuit { target } => {
+                let options = self.legal_cards_to_take(&target);
+                self.emit_to(
+                    Audience::Only(vec![whose_turn_reference]),
+                    GameEvent::CardsShown {
+                        from: target,
+                        to: whose_turn_reference,
+                        cards: options.clone(),
+                    },
+                );
+                self.pending = Some(PendingDecision::ChooseCardToTake {
+                    player: whose_turn_reference,
+                    target,
+                    options,
+                });
+                return Ok(());
+            }
+            Action::TractorBeam { target } => {
+                let random_card = self.remove_random_card(&target)?;
+                self.current_player().give(random_card);
+                self.emit_to(
+                    Audience::Only(vec![target, whose_turn_reference]),
+                    GameEvent::CardStolen {
+                        from: target,
+                        to: whose_turn_reference,
+                        card: random_card,
+                    },
+                );
+            }
+            Action::RocketBooster => {
+                self.add_space();
+                if self.pending.is_some() {
+                    return Ok(());
+                }
+            }
+            Action::LaserBlast { target } => {
+                let target_player = self.player_mut(&target)?;
+                if let Some(card) = target_player.space.pop() {
+                    self.space_deck.add_to_discard(card.clone());
+                    self.emit(GameEvent::SpaceCardLost {
+                        player: target,
+                        card,
+                    });
+                }
+            }
+            Action::HoleInSuit { target } => {
+                self.charge_oxygen(&target, 1, DeathCause::HoleInSuit);
+                if self.pending.is_some() {
+                    return Ok(());
+                }
+            }
+            Action::Tether { target } => {
+                // The target's last space card is pulled over to the end of the player's row.
+                // The card's effect is not triggered again.
+                let space_card = self
+                    .player_mut(&target)?
+                    .space
+                    .pop()
+                    .ok_or(SelfishError::PlayerHasNoSpace(target))?;
+                self.current_player().space.push(space_card.clone());
+                self.emit(GameEvent::SpaceCardPulled {
+                    from: target,
+                    to: whose_turn_reference,
+                    card: space_card,
+                });
+                self.check_reached_ship(&whose_turn_reference);
+            }
+        }
+
+        self.continue_actions()
+    }
+
+    fn discard(&mut self, card: &GameCard) -> miette::Result<()> {
+        self.current_player()
+            .remove_card(card)
+            .wrap_err_with(|| format!("Discarding {:?}", &card))?;
+        self.game_deck.add_to_discard(*card);
+        Ok(())
+    }
+
+    fn can_player_defend(&self, player_reference: &PlayerReference) -> miette::Result<bool> {
+        let player = self.player(player_reference)?;
+        let has_shield_card = player.hand.contains(&GameCard::Shield);
+        let in_solar_flare =
+            player.in_solar_flare() && !self.rules.house_rules.shields_in_solar_flare;
+        Ok(has_shield_card && !in_solar_flare)
+    }
+
+    fn remove_random_card(
+        &mut self,
+        player_reference: &PlayerReference,
+    ) -> miette::Result<GameCard> {
+        let (other_player, rng) = self.player_mut_rng(player_reference)?;
+        other_player.remove_random_card(rng)
+    }
+}
+
+/// Where the current player is in their turn.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Phase {
+    Pickup,
+    Actions,
+    BreatheOrTravel,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Phase::Pickup => "Pick up",
+            Phase::Actions => "Actions",
+            Phase::BreatheOrTravel => "Breathe or travel",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+fn fresh_beliefs(player_count: usize) -> Vec<BeliefState> {
+    (0..player_count)
+        .map(|idx| BeliefState::new(PlayerReference(idx), player_count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HouseRules, OxygenChange, Scenario};
+
+    fn new_state(players: usize) -> GameState {
+        GameState::new(Some(0), players)
+    }
+
+    /// Sets up player 0 to play the card against player 1.
+    fn state_with_card(card: GameCard) -> GameState {
+        let mut state = Scenario::new(2).deck([card]).build().unwrap();
+        state.start().unwrap();
+        state
+    }
+
+    fn play(state: &mut GameState, action: Action) -> miette::Result<Option<PendingDecision>> {
+        state.apply(Decision::PlayAction(Some(action)))
+    }
+
+    #[test]
+    fn test_hyperspace_onto_ship_skips_extra_card() {
+        let mut state = Scenario::new(2)
+            .ship_distance(2)
+            .hand(0, [GameCard::O2])
+            .space(0, [SpaceCard::BlankSpace])
+            .phase(Phase::Actions)
+            .space_deck([SpaceCard::Hyperspace])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        state.apply(Decision::PlayAction(None)).unwrap();
+        assert_eq!(state.winner(), Some(PlayerReference(0)));
+        assert_eq!(
+            state.players[0].space,
+            vec![SpaceCard::BlankSpace, SpaceCard::Hyperspace]
+        );
+    }
+
+    #[test]
+    fn test_o2_pays_for_cosmic_radiation_with_change() {
+        let mut state = Scenario::new(2)
+            .hand(0, [GameCard::O2, GameCard::O2])
+            .phase(Phase::Actions)
+            .space_deck([SpaceCard::CosmicRadiation])
+            .discard_pile([GameCard::O1])
+            .build()
+            .unwrap();
+        state.set_check_invariants(true);
+        state.start().unwrap();
+        state.apply(Decision::PlayAction(None)).unwrap();
+        assert!(state.players[0].alive);
+        assert_eq!(state.players[0].hand, vec![GameCard::O1]);
+        assert!(!state.discard_pile().contains(&GameCard::O1));
+        assert!(state.drain_events().contains(&GameEvent::ChangeGiven {
+            player: PlayerReference(0),
+            cards: vec![GameCard::O1],
+        }));
+    }
+
+    #[test]
+    fn test_o2_pays_without_change() {
+        let rules = RuleSet {
+            oxygen_change: OxygenChange::None,
+            ..RuleSet::default()
+        };
+        let mut state = Scenario::with_rules(2, rules)
+            .hand(0, [GameCard::O2, GameCard::O2])
+            .phase(Phase::Actions)
+            .space_deck([SpaceCard::CosmicRadiation])
+            .discard_pile([GameCard::O1])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        state.apply(Decision::PlayAction(None)).unwrap();
+        assert!(state.players[0].alive);
+        assert!(state.players[0].hand.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_payment() {
+        let mut state = state_with_card(GameCard::HoleInSuit);
+        let pending = play(
+            &mut state,
+            Action::HoleInSuit {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            pending,
+            Some(PendingDecision::PayOxygen {
+                player: PlayerReference(1),
+                cost: 1,
+                cause: DeathCause::HoleInSuit,
+                options: vec![vec![GameCard::O1], vec![GameCard::O2]],
+            })
+        );
+        assert!(state
+            .apply(Decision::PayOxygen(vec![GameCard::O1, GameCard::O1]))
+            .is_err());
+        state
+            .apply(Decision::PayOxygen(vec![GameCard::O2]))
+            .unwrap();
+        assert_eq!(state.players[1].count_cards(&GameCard::O1), 5);
+    }
+
+    #[test]
+    fn test_rocket_booster_onto_ship_ignores_hazard() {
+        let mut state = Scenario::new(2)
+            .ship_distance(1)
+            .hand(0, [GameCard::RocketBooster])
+            .phase(Phase::Actions)
+            .space_deck([SpaceCard::AsteroidField])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        play(&mut state, Action::RocketBooster).unwrap();
+        assert_eq!(state.winner(), Some(PlayerReference(0)));
+        assert!(state.players[0].alive);
+    }
+
+    #[test]
+    fn test_invariants_catch_lost_cards() {
+        let mut state = new_state(3);
+        state.start().unwrap();
+        assert_eq!(state.check_invariants(), Ok(()));
+
+        let card = state.players[1].hand.pop().unwrap();
+        assert_eq!(
+            state.check_invariants(),
+            Err(InvariantError::GameCardCount {
+                card,
+                expected: state.rules.decks.game_card_count(card),
+                tally: Tally {
+                    held: state.players.iter().map(|p| p.count_cards(&card)).sum(),
+                    in_deck: state
+                        .game_deck
+                        .available()
+                        .iter()
+                        .filter(|c| **c == card)
+                        .count(),
+                    discarded: 0,
+                },
+            })
+        );
+
+        state.players[1].hand.push(card);
+        state.players[1].alive = false;
+        assert!(matches!(
+            state.check_invariants(),
+            Err(InvariantError::DeadPlayerHoldsCards { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invariants_catch_wrong_phase() {
+        let mut state = new_state(2);
+        state.start().unwrap();
+        state.phase = Phase::BreatheOrTravel;
+        assert_eq!(
+            state.check_invariants(),
+            Err(InvariantError::WrongPhase {
+                pending: "play_action",
+                phase: Phase::BreatheOrTravel,
+            })
+        );
+    }
+
+    #[test]
+    fn test_discards_keep_the_card() {
+        let mut state = Scenario::new(2)
+            .hand(0, [GameCard::O2, GameCard::HoleInSuit])
+            .hand(1, [GameCard::O1, GameCard::O1])
+            .phase(Phase::Actions)
+            .build()
+            .unwrap();
+        state.set_check_invariants(true);
+        state.start().unwrap();
+        play(
+            &mut state,
+            Action::HoleInSuit {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(state.discard_pile(), &[GameCard::HoleInSuit, GameCard::O1]);
+
+        state.apply(Decision::PlayAction(None)).unwrap();
+        assert_eq!(state.discard_pile().last(), Some(&GameCard::O2));
+    }
+
+    #[test]
+    fn test_hole_in_suit_shielded() {
+        let mut state = state_with_card(GameCard::HoleInSuit);
+        state.players[1].give(GameCard::Shield);
+        let action = Action::HoleInSuit {
+            target: PlayerReference(1),
+        };
+        let pending = play(&mut state, action.clone()).unwrap();
+        assert_eq!(
+            pending,
+            Some(PendingDecision::Defend {
+                player: PlayerReference(1),
+                action,
+            })
+        );
+        state.apply(Decision::Defend(true)).unwrap();
+        assert_eq!(state.players[1].count_cards(&GameCard::O1), 4);
+        assert!(!state.players[1].has_card(&GameCard::Shield));
+    }
+
+    #[test]
+    fn test_hole_in_suit_cant_shield_in_solar_flare() {
+        let mut state = state_with_card(GameCard::HoleInSuit);
+        state.players[1].give(GameCard::Shield);
+        state.players[1].space.push(SpaceCard::SolarFlare);
+        play(
+            &mut state,
+            Action::HoleInSuit {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        state
+            .apply(Decision::PayOxygen(vec![GameCard::O1]))
+            .unwrap();
+        assert_eq!(state.players[1].count_cards(&GameCard::O1), 3);
+        assert!(state.players[1].has_card(&GameCard::Shield));
+    }
+
+    #[test]
+    fn test_house_rules_in_solar_flare() {
+        let rules = RuleSet {
+            house_rules: HouseRules {
+                actions_in_solar_flare: true,
+                shields_in_solar_flare: true,
+            },
+            ..RuleSet::default()
+        };
+        let mut state = Scenario::with_rules(2, rules)
+            .seed(3)
+            .deck([GameCard::HoleInSuit])
+            .space(0, [SpaceCard::SolarFlare])
+            .space(1, [SpaceCard::SolarFlare])
+            .hand(1, [GameCard::O2, GameCard::O1, GameCard::Shield])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+
+        let action = Action::HoleInSuit {
+            target: PlayerReference(1),
+        };
+        let pending = play(&mut state, action.clone()).unwrap();
+        assert_eq!(
+            pending,
+            Some(PendingDecision::Defend {
+                player: PlayerReference(1),
+                action,
+            })
+        );
+    }
+
+    #[test]
+    fn test_meteoroid_variant() {
+        let rules = RuleSet {
+            starting_o1: 3,
+            meteoroid_hand_limit: 4,
+            meteoroid_discard: 3,
+            ..RuleSet::default()
+        };
+        let state = GameState::with_rules(Some(3), 2, rules.clone()).unwrap();
+        assert_eq!(
+            state.players[1].hand,
+            vec![GameCard::O2, GameCard::O1, GameCard::O1, GameCard::O1]
+        );
+
+        let mut state = Scenario::with_rules(2, rules)
+            .hand(0, [GameCard::O2; 6])
+            .phase(Phase::Actions)
+            .space_deck([SpaceCard::Meteoroid])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        state.apply(Decision::PlayAction(None)).unwrap();
+        assert_eq!(
+            state.pending_decision(),
+            Some(&PendingDecision::ForcedDiscard {
+                player: PlayerReference(0),
+                card_count: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_oxygen_siphon_variant() {
+        let rules = RuleSet {
+            oxygen_siphon_count: 3,
+            ..RuleSet::default()
+        };
+        let mut state = Scenario::with_rules(2, rules)
+            .hand(0, [GameCard::O2, GameCard::OxygenSiphon])
+            .hand(1, [GameCard::O1, GameCard::O1, GameCard::Tether])
+            .phase(Phase::Actions)
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        play(
+            &mut state,
+            Action::OxygenSiphon {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(state.winner(), Some(PlayerReference(0)));
+    }
+
+    #[test]
+    fn test_tether_needs_space() {
+        let mut state = state_with_card(GameCard::Tether);
+        let result = play(
+            &mut state,
+            Action::Tether {
+                target: PlayerReference(1),
+            },
+        );
+        assert!(result.is_err());
+        assert!(state.players[0].has_card(&GameCard::Tether));
+        assert!(matches!(
+            state.pending_decision(),
+            Some(PendingDecision::PlayAction { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tether_shielded() {
+        let mut state = state_with_card(GameCard::Tether);
+        state.players[1].space = vec![SpaceCard::BlankSpace];
+        state.players[1].give(GameCard::Shield);
+        play(
+            &mut state,
+            Action::Tether {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        state.apply(Decision::Defend(true)).unwrap();
+        assert!(state.players[0].space.is_empty());
+        assert_eq!(state.players[1].space, vec![SpaceCard::BlankSpace]);
+    }
+
+    #[test]
+    fn test_tether_pulls_solar_flare() {
+        let mut state = state_with_card(GameCard::Tether);
+        state.players[1].space = vec![SpaceCard::SolarFlare];
+        play(
+            &mut state,
+            Action::Tether {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        assert!(state.players[0].in_solar_flare());
+        assert!(!state.players[1].in_solar_flare());
+    }
+
+    #[test]
+    fn test_tether_onto_ship() {
+        let mut state = Scenario::new(2)
+            .ship_distance(2)
+            .hand(0, [GameCard::O2, GameCard::Tether])
+            .space(0, [SpaceCard::BlankSpace])
+            .space(1, [SpaceCard::BlankSpace])
+            .phase(Phase::Actions)
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        play(
+            &mut state,
+            Action::Tether {
+                target: PlayerReference(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(state.winner(), Some(PlayerReference(0)));
+    }
+
+    #[test]
+    fn test_hole_in_suit_events() {
+        let mut state = state_with_card(GameCard::HoleInSuit);
+        state.players[1].hand = vec![GameCard::O1, GameCard::O1];
+        state.drain_events();
+        let action = Action::HoleInSuit {
+            target: PlayerReference(1),
+        };
+        play(&mut state, action.clone()).unwrap();
+        assert_eq!(
+            state.drain_events(),
+            vec![
+                GameEvent::ActionPlayed {
+                    player: PlayerReference(0),
+                    action,
+                },
+                GameEvent::CardsDiscarded {
+                    player: PlayerReference(1),
+                    cards: vec![GameCard::O1],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_decision() {
+        let mut state = new_state(2);
+        assert!(state.apply(Decision::PlayAction(None)).is_err());
+        state.start().unwrap();
+        assert!(state.apply(Decision::Defend(true)).is_err());
+        assert!(matches!(
+            state.pending_decision(),
+            Some(PendingDecision::PlayAction { .. })
+        ));
+    }
+
+    #[test]
+    fn test_legal_actions_match_engine() {
+        for seed in 0..50 {
+            let mut state = Scenario::new(3)
+                .seed(seed)
+                .hand(
+                    0,
+                    [
+                        GameCard::O2,
+                        GameCard::O1,
+                        GameCard::Tether,
+                        GameCard::LaserBlast,
+                        GameCard::RocketBooster,
+                    ],
+                )
+                .space(2, [SpaceCard::BlankSpace])
+                .deck([GameCard::HackSuit])
+                .build()
+                .unwrap();
+            state.start().unwrap();
+
+            let me = PlayerReference(0);
+            assert!(state.legal_actions(&PlayerReference(1)).is_empty());
+            assert!(!state.legal_actions(&me).contains(&Action::Tether {
+                target: PlayerReference(1)
+            }));
+            assert!(play(
+                &mut state,
+                Action::Tether {
+                    target: PlayerReference(1)
+                }
+            )
+            .is_err());
+            // Every legal action must be accepted by the engine.
+            for action in state.legal_actions(&me) {
+                let mut fork = state.clone();
+                play(&mut fork, action).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_legal_decisions_play_to_the_end() {
+        for seed in 0..10 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            for players in 2..=6 {
+                let mut state = GameState::new(Some(seed), players);
+                let mut pending = state.start().unwrap();
+                while pending.is_some() {
+                    let decisions = state.legal_decisions();
+                    let decision = decisions.choose(&mut rng).unwrap().clone();
+                    pending = state.apply(decision).unwrap();
+                }
+                assert!(
+                    state.is_game_over(),
+                    "seed {} with {} players",
+                    seed,
+                    players
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_deck_can_run_dry() {
+        // Only one card is left to draw after the deal, and hands can hold the rest.
+        let mut rules = RuleSet::default();
+        rules.decks.game_cards =
+            BTreeMap::from([(GameCard::O1, 8), (GameCard::O2, 2), (GameCard::Shield, 1)]);
+        for seed in 0..50 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut state = GameState::with_rules(Some(seed), 2, rules.clone()).unwrap();
+            state.set_check_invariants(true);
+            let mut pending = state.start().unwrap();
+            while pending.is_some() {
+                let decisions = state.legal_decisions();
+                let decision = decisions.choose(&mut rng).unwrap().clone();
+                pending = state.apply(decision).unwrap();
+            }
+            assert!(state.is_game_over(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_forks_are_independent() {
+        let mut state = new_state(3);
+        state.start().unwrap();
+        let mut fork = state.clone();
+        let players = state.players.clone();
+        fork.set_record_events(false);
+        while fork.pending_decision().is_some() {
+            let decision = fork.legal_decisions().remove(0);
+            fork.apply(decision).unwrap();
+        }
+        assert!(fork.is_game_over());
+        assert!(!state.is_game_over());
+        assert_eq!(state.players.len(), players.len());
+        for (player, before) in state.players.iter().zip(&players) {
+            assert_eq!(player.hand, before.hand);
+            assert_eq!(player.space, before.space);
+        }
+    }
+
+    #[test]
+    fn test_forks_are_deterministic() {
+        let mut state = new_state(4);
+        state.start().unwrap();
+        let mut a = state.clone();
+        let mut b = state.clone();
+        while a.pending_decision().is_some() {
+            let decision = a.legal_decisions().remove(0);
+            a.apply(decision.clone()).unwrap();
+            b.apply(decision).unwrap();
+        }
+        assert_eq!(a.drain_events(), b.drain_events());
+    }
+
+    #[test]
+    fn test_determinized_keeps_the_phase() {
+        // A rocket booster onto a meteoroid goes back to actions once the discard is made, where
+        // travelling onto it would end the turn.
+        let mut state = Scenario::new(2)
+            .hand(
+                0,
+                [
+                    GameCard::RocketBooster,
+                    GameCard::O2,
+                    GameCard::O1,
+                    GameCard::O1,
+                    GameCard::O1,
+                    GameCard::O1,
+                    GameCard::Tether,
+                    GameCard::LaserBlast,
+                ],
+            )
+            .phase(Phase::Actions)
+            .space_deck([SpaceCard::Meteoroid])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        let pending = play(&mut state, Action::RocketBooster).unwrap().unwrap();
+        assert_eq!(
+            pending,
+            PendingDecision::ForcedDiscard {
+                player: PlayerReference(0),
+                card_count: 2,
+            }
+        );
+
+        let view = state.visible_state(&PlayerReference(0)).unwrap();
+        let mut guess = GameState::determinized(&view, pending, &mut ChaCha8Rng::seed_from_u64(0));
+        let discard = Decision::ForcedDiscard(vec![GameCard::Tether, GameCard::LaserBlast]);
+        let expected = Some(PendingDecision::PlayAction {
+            player: PlayerReference(0),
+        });
+        assert_eq!(state.apply(discard.clone()).unwrap(), expected);
+        assert_eq!(guess.apply(discard).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_determinize_keeps_what_the_viewer_knows() {
+        let all_cards = |state: &GameState| {
+            let mut cards = state.game_deck.clone().take_available();
+            for player in &state.players {
+                cards.extend(player.hand.iter().copied());
+            }
+            cards.sort_by_key(|card| *card as usize);
+            cards
+        };
+        for seed in 0..10 {
+            let mut state = GameState::new(Some(seed), 4);
+            state.start().unwrap();
+            let viewer = state.whose_turn();
+            let before = all_cards(&state);
+
+            let mut guess = state.clone();
+            guess.determinize(&viewer, &mut ChaCha8Rng::seed_from_u64(seed));
+            assert_eq!(all_cards(&guess), before);
+            assert_eq!(guess.players[viewer.0].hand, state.players[viewer.0].hand);
+            for (guessed, actual) in guess.players.iter().zip(&state.players) {
+                assert_eq!(guessed.hand.len(), actual.hand.len());
+                assert_eq!(guessed.space, actual.space);
+            }
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use crate::actions::BreatheOrTravel;
+use crate::visible_state::{VisiblePlayer, VisibleState};
+use crate::{oxygen_value, Action, GameCard, PlayerController, PlayerReference, SpaceCard};
+use std::collections::HashSet;
+
+/// Knobs that change how a `HeuristicPlayerController` plays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Personality {
+    /// How keen the bot is to attack other players, from 0 (only when it helps itself) to 1.
+    pub aggression: f64,
+
+    /// Units of oxygen to keep in hand beyond what travelling the rest of the way costs, where an
+    /// O1 is one unit and an O2 two. Below this the bot breathes instead, staying out of the
+    /// hazards of space for a turn.
+    pub oxygen_reserve: usize,
+
+    /// How bad an attack has to be before a shield is spent on it, from 0 (always shield) to 1
+    /// (only when it would kill).
+    pub shield_threshold: f64,
+
+    /// How much more the bot cares about stopping the player closest to the ship.
+    pub leader_focus: f64,
+}
+
+impl Personality {
+    pub fn balanced() -> Self {
+        Self {
+            aggression: 0.6,
+            oxygen_reserve: 2,
+            shield_threshold: 0.3,
+            leader_focus: 1.0,
+        }
+    }
+
+    /// Attacks whenever it can and races for the ship.
+    pub fn aggressive() -> Self {
+        Self {
+            aggression: 1.0,
+            oxygen_reserve: 0,
+            shield_threshold: 0.6,
+            leader_focus: 0.5,
+        }
+    }
+
+    /// Hoards oxygen and shields, and only attacks the leader.
+    pub fn cautious() -> Self {
+        Self {
+            aggression: 0.2,
+            oxygen_reserve: 4,
+            shield_threshold: 0.0,
+            leader_focus: 2.0,
+        }
+    }
+}
+
+impl Default for Personality {
+    fn default() -> Self {
+        Self::balanced()
+    }
+}
+
+/// A bot that plays by rules of thumb, looking only at what it can see.
+///
+/// It weighs its oxygen against how far it is from the ship, saves shields for the attacks that
+/// hurt, and goes after whoever is closest to winning. Every choice is deterministic given the
+/// visible state.
+pub struct HeuristicPlayerController {
+    personality: Personality,
+    visible_state: VisibleState,
+}
+
+impl HeuristicPlayerController {
+    pub fn new(personality: Personality) -> Self {
+        Self {
+            personality,
+            visible_state: VisibleState::invalid(),
+        }
+    }
+
+    pub fn personality(&self) -> &Personality {
+        &self.personality
+    }
+
+    fn me(&self) -> Option<&VisiblePlayer> {
+        self.visible_state.players.get(self.visible_state.viewer.0)
+    }
+
+    /// Oxygen in hand, counting an O2 as two.
+    fn oxygen(&self) -> usize {
+        self.visible_state.my_hand.iter().map(oxygen_value).sum()
+    }
+
+    /// How much of a danger the player is, mostly from how close they are to the ship.
+    fn threat(&self, target: &PlayerReference) -> f64 {
+        let state = &self.visible_state;
+        let player = match state.players.get(target.0) {
+            None => return 0.0,
+            Some(player) => player,
+        };
+        let progress = player.space.len() as f64 / state.ship_distance.max(1) as f64;
+
+        let furthest = state
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(idx, other)| *idx != state.viewer.0 && other.alive)
+            .map(|(_, other)| other.space.len())
+            .max()
+            .unwrap_or(0);
+        let is_leader = furthest > 0 && player.space.len() == furthest;
+        let leader_bonus = if is_leader {
+            self.personality.leader_focus * 0.5
+        } else {
+            0.0
+        };
+
+        progress + leader_bonus
+    }
+
+    /// How likely an attack on their oxygen is to kill them, guessed from their hand size.
+    fn vulnerability(&self, target: &PlayerReference) -> f64 {
+        match self.visible_state.players.get(target.0) {
+            None => 0.0,
+            Some(player) => 1.0 / player.hand_size.max(1) as f64,
+        }
+    }
+
+    /// Positive when the action is worth playing, higher is better.
+    fn score_action(&self, action: &Action) -> f64 {
+        let aggression = self.personality.aggression;
+        let hand_size = |target: &PlayerReference| {
+            self.visible_state
+                .players
+                .get(target.0)
+                .map_or(0, |player| player.hand_size)
+        };
+        let last_space = |target: &PlayerReference| {
+            self.visible_state
+                .players
+                .get(target.0)
+                .and_then(|player| player.space.last().cloned())
+        };
+
+        match action {
+            Action::RocketBooster => 1.0,
+            Action::Tether { target } => {
+                // Pulling a solar flare over would stop us playing cards.
+                if last_space(target) == Some(SpaceCard::SolarFlare) {
+                    return -1.0;
+                }
+                1.0 + aggression * self.threat(target)
+            }
+            Action::LaserBlast { target } => {
+                // Blasting away their solar flare would only help them.
+                if last_space(target) == Some(SpaceCard::SolarFlare) {
+                    return -1.0;
+                }
+                aggression * self.threat(target) * 2.0
+            }
+            Action::OxygenSiphon { target } => {
+                0.5 + aggression * (0.5 + self.threat(target) + self.vulnerability(target))
+            }
+            Action::HoleInSuit { target } => {
+                aggression * (0.5 + self.threat(target) + self.vulnerability(target))
+            }
+            Action::TractorBeam { target } | Action::HackSuit { target } => {
+                // Bigger hands have more to steal.
+                let hand_size = hand_size(target) as f64;
+                0.5 + 0.1 * hand_size + aggression * self.threat(target) * 0.5
+            }
+        }
+    }
+
+    /// How much the bot wants to keep a card in hand.
+    fn card_value(&self, card: &GameCard) -> f64 {
+        match card {
+            GameCard::O2 => 4.0,
+            GameCard::O1 => 3.0,
+            GameCard::Shield => 2.0 + (1.0 - self.personality.shield_threshold),
+            GameCard::RocketBooster | GameCard::Tether => 2.0,
+            GameCard::OxygenSiphon | GameCard::HackSuit | GameCard::TractorBeam => {
+                1.0 + self.personality.aggression
+            }
+            GameCard::LaserBlast | GameCard::HoleInSuit => 0.5 + self.personality.aggression,
+        }
+    }
+
+    /// How much the attack would hurt, from 0 to 1.
+    fn severity(&self, action: &Action) -> f64 {
+        let oxygen = self.oxygen();
+        let siphoned = self.visible_state.rules.oxygen_siphon_count;
+        let progress = match self.me() {
+            None => 0.0,
+            Some(me) => me.space.len() as f64 / self.visible_state.ship_distance.max(1) as f64,
+        };
+
+        match action {
+            // Fatal without the oxygen to cover it, counting the change an O2 would get.
+            Action::OxygenSiphon { .. } if oxygen < siphoned => 1.0,
+            Action::HoleInSuit { .. } if oxygen == 0 => 1.0,
+            Action::OxygenSiphon { .. } => 0.8,
+            Action::HoleInSuit { .. } => 0.6,
+            Action::LaserBlast { .. } | Action::Tether { .. } => 0.3 + 0.6 * progress,
+            Action::HackSuit { .. } => 0.4,
+            Action::TractorBeam { .. } => 0.3,
+            Action::RocketBooster => 0.0,
+        }
+    }
+}
+
+impl Default for HeuristicPlayerController {
+    fn default() -> Self {
+        Self::new(Personality::default())
+    }
+}
+
+impl PlayerController for HeuristicPlayerController {
+    fn update_state(&mut self, visible_state: VisibleState) {
+        self.visible_state = visible_state;
+    }
+
+    fn play_action(&mut self) -> Option<Action> {
+        let mut best: Option<(f64, Action)> = None;
+        for action in self.visible_state.legal_actions() {
+            let score = self.score_action(&action);
+            if score > 0.0 && best.as_ref().is_none_or(|(best, _)| score > *best) {
+                best = Some((score, action));
+            }
+        }
+        best.map(|(_, action)| action)
+    }
+
+    fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        let state = &self.visible_state;
+        let me = match self.me() {
+            None => return BreatheOrTravel::Travel,
+            Some(me) => me,
+        };
+
+        // One more step wins, and a solar flare is worth leaving behind.
+        let remaining = state.ship_distance.saturating_sub(me.space.len());
+        if remaining <= 1 || me.in_solar_flare() {
+            return BreatheOrTravel::Travel;
+        }
+
+        // Travel while the hand covers the rest of the way, as hazards can be paid with O2s too.
+        if self.oxygen().saturating_sub(2 * remaining) >= self.personality.oxygen_reserve {
+            BreatheOrTravel::Travel
+        } else {
+            BreatheOrTravel::Breathe
+        }
+    }
+
+    /// Shield anything that would be fatal, and anything else at least as bad as the threshold.
+    fn defend(&mut self, action: &Action) -> bool {
+        let severity = self.severity(action);
+        severity >= 1.0 || severity >= self.personality.shield_threshold
+    }
+
+    fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+        let value =
+            |cards: &Vec<GameCard>| -> f64 { cards.iter().map(|card| self.card_value(card)).sum() };
+        let mut best: Option<(f64, Vec<GameCard>)> = None;
+        for discard in self.visible_state.legal_discards(card_count) {
+            let cost = value(&discard);
+            if best.as_ref().is_none_or(|(best, _)| cost < *best) {
+                best = Some((cost, discard));
+            }
+        }
+        best.map(|(_, discard)| discard).unwrap_or_default()
+    }
+
+    /// Take the row that is furthest along, unless it ends in a solar flare.
+    fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+        let state = &self.visible_state;
+        let score = |target: &PlayerReference| -> f64 {
+            let player = &state.players[target.0];
+            let flare = if player.in_solar_flare() { 1.5 } else { 0.0 };
+            player.space.len() as f64 - flare
+        };
+
+        let mut best: Option<(f64, PlayerReference)> = None;
+        for target in state.legal_swap_targets() {
+            let score = score(&target);
+            if best.is_none_or(|(best, _)| score > best) {
+                best = Some((score, target));
+            }
+        }
+        best.map(|(_, target)| target).unwrap_or(state.viewer)
+    }
+
+    fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+        let mut options: Vec<GameCard> = options.into_iter().collect();
+        // The set has no order, so sort for a deterministic choice between equal values.
+        options.sort_by_key(|card| *card as usize);
+        let mut best = options[0];
+        for card in options {
+            if self.card_value(&card) > self.card_value(&best) {
+                best = card;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visible_state::visible_player as player;
+    use crate::{Game, RandomPlayerController};
+
+    fn controller(
+        my_hand: Vec<GameCard>,
+        players: Vec<VisiblePlayer>,
+    ) -> HeuristicPlayerController {
+        let mut controller = HeuristicPlayerController::default();
+        controller.update_state(VisibleState {
+            viewer: PlayerReference(0),
+            whose_turn: PlayerReference(0),
+            ship_distance: 6,
+            rules: Default::default(),
+            my_hand,
+            players,
+            ..VisibleState::invalid()
+        });
+        controller
+    }
+
+    #[test]
+    fn test_travels_onto_ship() {
+        let mut controller = controller(
+            vec![GameCard::O1, GameCard::O2],
+            vec![player(2, vec![SpaceCard::BlankSpace; 5]), player(5, vec![])],
+        );
+        assert_eq!(controller.breathe_or_travel(), BreatheOrTravel::Travel);
+    }
+
+    #[test]
+    fn test_breathes_when_low_on_oxygen() {
+        let mut controller = controller(
+            vec![GameCard::O1, GameCard::O2],
+            vec![player(2, vec![SpaceCard::BlankSpace]), player(5, vec![])],
+        );
+        assert_eq!(controller.breathe_or_travel(), BreatheOrTravel::Breathe);
+    }
+
+    #[test]
+    fn test_shields_lethal_attacks() {
+        let mut controller = controller(
+            vec![GameCard::Tether, GameCard::Shield],
+            vec![player(2, vec![]), player(5, vec![])],
+        );
+        controller.personality.shield_threshold = 1.0;
+        assert!(controller.defend(&Action::HoleInSuit {
+            target: PlayerReference(0)
+        }));
+        assert!(!controller.defend(&Action::TractorBeam {
+            target: PlayerReference(0)
+        }));
+    }
+
+    #[test]
+    fn test_lasers_the_leader() {
+        let mut controller = controller(
+            vec![GameCard::LaserBlast],
+            vec![
+                player(1, vec![]),
+                player(5, vec![SpaceCard::BlankSpace]),
+                player(5, vec![SpaceCard::BlankSpace; 3]),
+            ],
+        );
+        assert_eq!(
+            controller.play_action(),
+            Some(Action::LaserBlast {
+                target: PlayerReference(2)
+            })
+        );
+    }
+
+    #[test]
+    fn test_wormhole_avoids_solar_flare() {
+        let mut controller = controller(
+            vec![],
+            vec![
+                player(1, vec![SpaceCard::WormHole]),
+                player(5, vec![SpaceCard::BlankSpace, SpaceCard::SolarFlare]),
+                player(5, vec![SpaceCard::BlankSpace]),
+            ],
+        );
+        assert_eq!(controller.choose_player_to_swap_with(), PlayerReference(2));
+    }
+
+    #[test]
+    fn test_discards_least_useful_cards() {
+        let mut controller = controller(
+            vec![
+                GameCard::O1,
+                GameCard::O2,
+                GameCard::LaserBlast,
+                GameCard::Shield,
+                GameCard::HoleInSuit,
+            ],
+            vec![player(5, vec![]), player(5, vec![])],
+        );
+        let mut discard = controller.forced_discard(2);
+        discard.sort_by_key(|card| *card as usize);
+        assert_eq!(discard, vec![GameCard::LaserBlast, GameCard::HoleInSuit]);
+    }
+
+    /// Games won by the heuristic side at a table of two heuristic and two seeded random
+    /// players, who swap seats every game so that neither side always goes first.
+    fn heuristic_wins(games: u64) -> usize {
+        (0..games)
+            .filter(|seed| {
+                let controllers: Vec<Box<dyn PlayerController>> = (0..4)
+                    .map(|seat| -> Box<dyn PlayerController> {
+                        if (seat + seed).is_multiple_of(2) {
+                            Box::new(HeuristicPlayerController::default())
+                        } else {
+                            Box::new(RandomPlayerController::with_seed(seed * 10 + seat))
+                        }
+                    })
+                    .collect();
+                let mut game = Game::new(Some(*seed), controllers);
+                game.simulate().unwrap();
+                let winner = game.winner().unwrap().0 as u64;
+                (winner + seed).is_multiple_of(2)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_beats_random_players() {
+        // Without any skill each side wins half of the 300 games, 150 with a standard deviation
+        // of 8.7, so 171 or more would happen by chance less than 1% of the time. The heuristic
+        // side wins about 62%, or 185.
+        assert!(heuristic_wins(300) >= 171);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{GameCard, Phase, PlayerReference, SpaceCard};
+use miette::Diagnostic;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// Something that can never happen in a correct engine, found by
+/// `GameState::check_invariants`.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Diagnostic)]
+#[diagnostic(help("This is a bug in the engine, not in a controller."))]
+pub enum InvariantError {
+    #[error("The decks have {expected} {card:?} cards but there are {tally}.")]
+    GameCardCount {
+        card: GameCard,
+        expected: usize,
+        tally: Tally,
+    },
+
+    #[error("The decks have {expected} {card:?} cards but there are {tally}.")]
+    SpaceCardCount {
+        card: SpaceCard,
+        expected: usize,
+        tally: Tally,
+    },
+
+    #[error("Player {player:?} is dead but still holds {cards:?}.")]
+    DeadPlayerHoldsCards {
+        player: PlayerReference,
+        cards: Vec<GameCard>,
+    },
+
+    #[error("The game is waiting on dead player {0:?}.")]
+    DeadPlayerDeciding(PlayerReference),
+
+    #[error("It is dead player {0:?}'s turn.")]
+    DeadPlayersTurn(PlayerReference),
+
+    #[error("The game is still going but isn't waiting for a decision.")]
+    NothingPending,
+
+    #[error("The game is over but is waiting for a {0} decision.")]
+    PendingAfterGameOver(&'static str),
+
+    #[error("A {pending} decision can't be pending in the {phase} phase.")]
+    WrongPhase { pending: &'static str, phase: Phase },
+
+    #[error(
+        "A {pending} decision is waiting on player {player:?} during player {whose_turn:?}'s turn."
+    )]
+    WrongPlayer {
+        pending: &'static str,
+        player: PlayerReference,
+        whose_turn: PlayerReference,
+    },
+}
+
+/// Where the copies of one card are. Held means in hands for game cards, and in space rows for
+/// space cards.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub held: usize,
+    pub in_deck: usize,
+    pub discarded: usize,
+}
+
+impl Tally {
+    pub fn total(&self) -> usize {
+        self.held + self.in_deck + self.discarded
+    }
+}
+
+impl Display for Tally {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} held, {} in the deck and {} discarded",
+            self.total(),
+            self.held,
+            self.in_deck,
+            self.discarded
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, PlayerController, RandomPlayerController};
+
+    #[test]
+    fn test_random_games_keep_invariants() {
+        for player_count in 2..=6 {
+            for seed in 0..20 {
+                let controllers = (0..player_count)
+                    .map(|seat| {
+                        Box::new(RandomPlayerController::with_seed(seed * 10 + seat))
+                            as Box<dyn PlayerController>
+                    })
+                    .collect();
+                let mut game = Game::new(Some(seed), controllers);
+                game.set_check_invariants(true);
+                if let Err(err) = game.simulate() {
+                    panic!("seed {} with {} players: {:?}", seed, player_count, err);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use crate::actions::BreatheOrTravel;
+use crate::decision::{Decision, PendingDecision};
+use crate::visible_state::VisibleState;
+use crate::{
+    Action, GameCard, GameState, HeuristicPlayerController, PlayerController, PlayerReference,
+};
+use rand::prelude::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// How much searching an `IsmctsPlayerController` does per decision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsmctsConfig {
+    /// Simulations to run for each decision.
+    pub iterations: usize,
+
+    /// Stop early once this much time has been spent on a decision.
+    pub time_budget: Option<Duration>,
+
+    /// How much to favour less explored moves over ones that have won so far.
+    pub exploration: f64,
+
+    /// Give up on a random playout after this many decisions and count it as a loss for everyone.
+    pub max_playout_decisions: usize,
+}
+
+impl Default for IsmctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            time_budget: None,
+            exploration: 0.7,
+            max_playout_decisions: 1000,
+        }
+    }
+}
+
+/// Information Set Monte Carlo Tree Search.
+///
+/// Each simulation guesses the hidden hands and deck order from the `VisibleState`, then plays
+/// the guess out on a `GameState`, so the search uses exactly the same rules as the game. The
+/// statistics of every guess are shared in one tree, and the most visited move is played.
+///
+/// Choosing which card a hack suit takes is left to a `HeuristicPlayerController`.
+pub struct IsmctsPlayerController {
+    config: IsmctsConfig,
+    rng: ChaCha8Rng,
+    visible_state: VisibleState,
+    fallback: HeuristicPlayerController,
+}
+
+impl IsmctsPlayerController {
+    pub fn new(config: IsmctsConfig) -> Self {
+        Self::with_rng(config, ChaCha8Rng::from_entropy())
+    }
+
+    /// Makes the same choices every time, given the same game.
+    pub fn with_seed(config: IsmctsConfig, seed: u64) -> Self {
+        Self::with_rng(config, ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn with_rng(config: IsmctsConfig, rng: ChaCha8Rng) -> Self {
+        Self {
+            config,
+            rng,
+            visible_state: VisibleState::invalid(),
+            fallback: HeuristicPlayerController::default(),
+        }
+    }
+
+    pub fn config(&self) -> &IsmctsConfig {
+        &self.config
+    }
+
+    fn search(&mut self, pending: &PendingDecision) -> Decision {
+        let root_state =
+            GameState::determinized(&self.visible_state, pending.clone(), &mut self.rng);
+        let legal = root_state.legal_decisions();
+        if legal.len() <= 1 {
+            return legal
+                .into_iter()
+                .next()
+                .unwrap_or(Decision::PlayAction(None));
+        }
+
+        let started = Instant::now();
+        let mut tree = Tree::new();
+        for _ in 0..self.config.iterations {
+            if let Some(time_budget) = self.config.time_budget {
+                if started.elapsed() >= time_budget {
+                    break;
+                }
+            }
+            self.iterate(&mut tree, pending);
+        }
+
+        // The most visited move is the most trusted one.
+        tree.nodes[0]
+            .children
+            .iter()
+            .map(|idx| &tree.nodes[*idx])
+            .filter(|node| legal.contains(&node.decision))
+            .max_by(|a, b| a.visits.total_cmp(&b.visits))
+            .map(|node| node.decision.clone())
+            .unwrap_or_else(|| legal[0].clone())
+    }
+
+    /// One simulation: guess the hidden information, walk down the tree, add a node, play out
+    /// randomly and record who won.
+    fn iterate(&mut self, tree: &mut Tree, pending: &PendingDecision) {
+        let mut state =
+            GameState::determinized(&self.visible_state, pending.clone(), &mut self.rng);
+        let mut path = vec![0];
+        let mut node = 0;
+
+        while let Some(pending) = state.pending_decision() {
+            let player = pending.player();
+            let legal = state.legal_decisions();
+
+            let untried: Vec<&Decision> = legal
+                .iter()
+                .filter(|decision| !tree.has_child(node, decision))
+                .collect();
+            if let Some(decision) = untried.choose(&mut self.rng) {
+                let decision = (*decision).clone();
+                if state.apply(decision.clone()).is_err() {
+                    return;
+                }
+                node = tree.add_child(node, player, decision);
+                path.push(node);
+                break;
+            }
+
+            let child = tree.select(node, &legal, self.config.exploration);
+            if state.apply(tree.nodes[child].decision.clone()).is_err() {
+                return;
+            }
+            node = child;
+            path.push(node);
+        }
+
+        // Play the rest of the game out at random.
+        let mut remaining = self.config.max_playout_decisions;
+        while state.pending_decision().is_some() && remaining > 0 {
+            let decisions = state.legal_decisions();
+            let decision = match decisions.choose(&mut self.rng) {
+                None => break,
+                Some(decision) => decision.clone(),
+            };
+            if state.apply(decision).is_err() {
+                break;
+            }
+            remaining -= 1;
+        }
+
+        let winner = state.winner();
+        for idx in path {
+            let node = &mut tree.nodes[idx];
+            node.visits += 1.0;
+            if node.player.is_some() && node.player == winner {
+                node.wins += 1.0;
+            }
+        }
+    }
+}
+
+impl Default for IsmctsPlayerController {
+    fn default() -> Self {
+        Self::new(IsmctsConfig::default())
+    }
+}
+
+impl PlayerController for IsmctsPlayerController {
+    fn update_state(&mut self, visible_state: VisibleState) {
+        self.fallback.update_state(visible_state.clone());
+        self.visible_state = visible_state;
+    }
+
+    fn play_action(&mut self) -> Option<Action> {
+        let me = self.visible_state.viewer;
+        match self.decide(&PendingDecision::PlayAction { player: me }) {
+            Decision::PlayAction(action) => action,
+            _ => None,
+        }
+    }
+
+    fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        let me = self.visible_state.viewer;
+        match self.decide(&PendingDecision::BreatheOrTravel { player: me }) {
+            Decision::BreatheOrTravel(choice) => choice,
+            _ => BreatheOrTravel::Travel,
+        }
+    }
+
+    fn defend(&mut self, action: &Action) -> bool {
+        let me = self.visible_state.viewer;
+        match self.decide(&PendingDecision::Defend {
+            player: me,
+            action: action.clone(),
+        }) {
+            Decision::Defend(defend) => defend,
+            _ => false,
+        }
+    }
+
+    fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+        let me = self.visible_state.viewer;
+        match self.decide(&PendingDecision::ForcedDiscard {
+            player: me,
+            card_count,
+        }) {
+            Decision::ForcedDiscard(cards) => cards,
+            _ => Vec::new(),
+        }
+    }
+
+    fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+        let me = self.visible_state.viewer;
+        match self.decide(&PendingDecision::ChooseSwapTarget { player: me }) {
+            Decision::SwapWith(target) => target,
+            _ => me,
+        }
+    }
+
+    /// Without the target's hand there is nothing to search, so take the most useful card.
+    fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+        self.fallback.choose_card_to_take(options)
+    }
+
+    fn decide(&mut self, pending: &PendingDecision) -> Decision {
+        match pending {
+            PendingDecision::ChooseCardToTake { options, .. } => {
+                Decision::TakeCard(self.choose_card_to_take(options.iter().copied().collect()))
+            }
+            _ => self.search(pending),
+        }
+    }
+}
+
+/// The search tree, with the root at index 0.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    /// The decision that led here from the parent. Unused for the root.
+    decision: Decision,
+    /// Who made the decision, and so who a win here counts for.
+    player: Option<PlayerReference>,
+    children: Vec<usize>,
+    visits: f64,
+    wins: f64,
+    /// How many times the node could have been picked, since a guess at the hidden information
+    /// may rule it out.
+    availability: f64,
+}
+
+impl Tree {
+    fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                decision: Decision::PlayAction(None),
+                player: None,
+                children: Vec::new(),
+                visits: 0.0,
+                wins: 0.0,
+                availability: 0.0,
+            }],
+        }
+    }
+
+    fn has_child(&self, node: usize, decision: &Decision) -> bool {
+        self.nodes[node]
+            .children
+            .iter()
+            .any(|child| self.nodes[*child].decision == *decision)
+    }
+
+    fn add_child(&mut self, node: usize, player: PlayerReference, decision: Decision) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            decision,
+            player: Some(player),
+            children: Vec::new(),
+            visits: 0.0,
+            wins: 0.0,
+            availability: 1.0,
+        });
+        self.nodes[node].children.push(idx);
+        idx
+    }
+
+    /// Pick the legal child with the best upper confidence bound.
+    fn select(&mut self, node: usize, legal: &[Decision], exploration: f64) -> usize {
+        let children: Vec<usize> = self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .filter(|child| legal.contains(&self.nodes[*child].decision))
+            .collect();
+        for child in &children {
+            self.nodes[*child].availability += 1.0;
+        }
+
+        let score = |node: &Node| {
+            let visits = node.visits.max(1.0);
+            node.wins / visits + exploration * (node.availability.ln() / visits).sqrt()
+        };
+        children
+            .into_iter()
+            .max_by(|a, b| score(&self.nodes[*a]).total_cmp(&score(&self.nodes[*b])))
+            .expect("every legal decision has been tried")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visible_state::{visible_player as player, VisiblePlayer};
+    use crate::{Game, Phase, RandomPlayerController, RuleSet, SpaceCard};
+    use std::sync::Arc;
+
+    fn controller(my_hand: Vec<GameCard>, players: Vec<VisiblePlayer>) -> IsmctsPlayerController {
+        let config = IsmctsConfig {
+            iterations: 200,
+            ..IsmctsConfig::default()
+        };
+        let mut controller = IsmctsPlayerController::with_seed(config, 0);
+        let rules: Arc<RuleSet> = Default::default();
+        let cards: usize = rules.decks.game_cards.values().sum();
+        let held: usize = players.iter().map(|player| player.hand_size).sum();
+        controller.update_state(VisibleState {
+            viewer: PlayerReference(0),
+            whose_turn: PlayerReference(0),
+            ship_distance: 6,
+            deck_size: cards - held,
+            space_deck: rules.decks.space_cards.clone(),
+            rules,
+            my_hand,
+            players,
+            ..VisibleState::invalid()
+        });
+        controller
+    }
+
+    #[test]
+    fn test_same_seed_same_choices() {
+        // Every guess at the hidden cards comes from the seed, so the search plays out the same.
+        let choices = || {
+            let mut controller = controller(
+                vec![
+                    GameCard::O1,
+                    GameCard::O2,
+                    GameCard::LaserBlast,
+                    GameCard::Tether,
+                ],
+                vec![
+                    player(4, vec![SpaceCard::BlankSpace; 2]),
+                    player(5, vec![SpaceCard::BlankSpace; 2]),
+                    player(5, vec![SpaceCard::BlankSpace]),
+                ],
+            );
+            (0..3).map(|_| controller.play_action()).collect::<Vec<_>>()
+        };
+        assert_eq!(choices(), choices());
+    }
+
+    #[test]
+    fn test_tethers_onto_ship() {
+        let mut controller = controller(
+            vec![GameCard::O1, GameCard::Tether, GameCard::LaserBlast],
+            vec![
+                player(3, vec![SpaceCard::BlankSpace; 5]),
+                // Blasting this away first would leave nothing to tether.
+                player(5, vec![SpaceCard::BlankSpace]),
+            ],
+        );
+        assert_eq!(
+            controller.play_action(),
+            Some(Action::Tether {
+                target: PlayerReference(1)
+            })
+        );
+    }
+
+    #[test]
+    fn test_shields_a_winning_tether() {
+        let mut controller = controller(
+            vec![GameCard::Shield, GameCard::O1, GameCard::O2],
+            vec![
+                player(3, vec![SpaceCard::BlankSpace; 5]),
+                player(4, vec![SpaceCard::BlankSpace; 5]),
+            ],
+        );
+        let mut view = controller.visible_state.clone();
+        view.whose_turn = PlayerReference(1);
+        view.phase = Phase::Actions;
+        controller.update_state(view);
+        // Without the shield player 1 pulls over the last card and reaches the ship.
+        assert!(controller.defend(&Action::Tether {
+            target: PlayerReference(0)
+        }));
+    }
+
+    #[test]
+    fn test_respects_time_budget() {
+        let mut controller = controller(
+            vec![GameCard::O1, GameCard::O2, GameCard::LaserBlast],
+            vec![
+                player(3, vec![SpaceCard::BlankSpace]),
+                player(5, vec![SpaceCard::BlankSpace]),
+            ],
+        );
+        controller.config.iterations = usize::MAX;
+        controller.config.time_budget = Some(Duration::from_millis(50));
+        let started = Instant::now();
+        controller.play_action();
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_plays_legal_games() {
+        for seed in 0..3 {
+            let config = IsmctsConfig {
+                iterations: 20,
+                ..IsmctsConfig::default()
+            };
+            let controllers: Vec<Box<dyn PlayerController>> = vec![
+                Box::new(IsmctsPlayerController::with_seed(config, seed)),
+                Box::new(RandomPlayerController::with_seed(seed)),
+                Box::new(HeuristicPlayerController::default()),
+            ];
+            let mut game = Game::new(Some(seed), controllers);
+            let rejected = std::rc::Rc::new(std::cell::Cell::new(0));
+            let counter = rejected.clone();
+            game.add_observer(Box::new(move |_: &Game, event: &crate::GameEvent| {
+                if matches!(event, crate::GameEvent::DecisionRejected { .. }) {
+                    counter.set(counter.get() + 1);
+                }
+            }));
+            game.simulate().unwrap();
+            assert!(game.winner().is_some());
+            assert_eq!(rejected.get(), 0);
+        }
+    }
+}
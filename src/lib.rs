@@ -0,0 +1,121 @@
+//! A simulator for the card game Selfish: Space Edition.
+//!
+//! Build a [`Game`] with one [`PlayerController`] per seat and run it to completion:
+//!
+//! ```
+//! use selfish::{Game, PlayerController, RandomPlayerController};
+//!
+//! let controllers: Vec<Box<dyn PlayerController>> = (0..4)
+//!     .map(|_| Box::new(RandomPlayerController::new()) as Box<dyn PlayerController>)
+//!     .collect();
+//! let mut game = Game::new(Some(42), controllers);
+//! game.simulate().unwrap();
+//! assert!(game.winner().is_some());
+//! ```
+//!
+//! Or drive it one decision at a time, e.g. from a UI event loop, answering each
+//! [`PendingDecision`] with a [`Decision`]:
+//!
+//! ```
+//! use selfish::{Decision, Game, PendingDecision};
+//!
+//! let mut game = Game::headless(Some(42), 2);
+//! let mut pending = game.start().unwrap();
+//! while let Some(decision) = pending {
+//!     let answer = match decision {
+//!         // Never play an action card.
+//!         PendingDecision::PlayAction { .. } => Decision::PlayAction(None),
+//!         _ => game.state().legal_decisions().remove(0),
+//!     };
+//!     pending = game.submit(answer).unwrap();
+//! }
+//! assert!(game.winner().is_some());
+//! ```
+//!
+//! Everything that happens is reported as a [`GameEvent`] to any [`GameObserver`]s, such as the
+//! [`TerminalObserver`] which prints the game as it is played. Some events, like the card a player
+//! drew, are private to the players involved; a [`SeatedObserver`] only hears what its seat saw.
+//!
+//! The rules themselves live in [`GameState`], which knows nothing about controllers. It stops at
+//! every [`PendingDecision`] and can be cloned to look ahead, e.g. for search-based players.
+//!
+//! Controllers only ever see a [`VisibleState`], which holds what a fair player could observe at
+//! the table, including what their [`BeliefState`] says is in the other hands.
+//!
+//! To start from a particular position instead of a fresh deal, e.g. for rules tests or puzzles,
+//! describe it with a [`Scenario`].
+//!
+//! The `fuzz` feature adds a `Fuzzer` that plays games against controllers answering with
+//! garbage, to check the engine copes.
+
+mod actions;
+mod analysis;
+mod belief;
+mod decision;
+mod decks;
+mod errors;
+mod events;
+#[cfg(any(test, feature = "fuzz"))]
+// Without the feature only the tests use it.
+#[cfg_attr(not(feature = "fuzz"), allow(dead_code))]
+mod fuzz;
+mod game;
+mod game_cards;
+mod game_state;
+mod heuristic_player_controller;
+mod invariants;
+mod ismcts_player_controller;
+mod misconduct;
+mod oxygen;
+mod player;
+mod player_controller;
+mod replay;
+#[cfg(test)]
+mod rulebook;
+mod rules;
+mod scenario;
+mod space_cards;
+mod terminal;
+mod terminal_player_controller;
+mod tournament;
+#[cfg(feature = "tui")]
+mod tui;
+mod visible_state;
+
+pub use actions::{Action, ActionRules, BreatheOrTravel, Steal, StealAccess};
+pub use analysis::Analysis;
+pub use belief::BeliefState;
+pub use decision::{Decision, PendingDecision};
+pub use decks::{DeckConfig, DeckError, BASE_DECKS};
+pub use errors::SelfishError;
+pub use events::{Audience, DeathCause, GameEvent, GameObserver, SeatedObserver, WinReason};
+#[cfg(feature = "fuzz")]
+pub use fuzz::{
+    AdversarialController, FuzzConfig, FuzzError, FuzzFailure, FuzzFailureKind, FuzzReport, Fuzzer,
+};
+pub use game::{Game, PlayerReference};
+pub use game_cards::GameCard;
+pub use game_state::{GameState, Phase};
+pub use heuristic_player_controller::{HeuristicPlayerController, Personality};
+pub use invariants::{InvariantError, Tally};
+pub use ismcts_player_controller::{IsmctsConfig, IsmctsPlayerController};
+pub use misconduct::{fallback_decision, MisconductPolicy, Penalty};
+pub use oxygen::{oxygen_value, payments, OxygenChange};
+pub use player::Player;
+pub use player_controller::{PlayerController, RandomPlayerController};
+pub use replay::{RecordedDecision, Recorder, Replay, ReplayError, REPLAY_VERSION};
+pub use rules::{HouseRules, RuleError, RuleSet};
+pub use scenario::{Pile, Scenario, ScenarioError, ScenarioPlayer};
+pub use space_cards::SpaceCard;
+pub use terminal::TerminalObserver;
+pub use terminal_player_controller::{MenuError, TerminalPlayerController};
+pub use tournament::{
+    seat_seed, ControllerFactory, EntrantStats, Estimate, GameRecord, ScheduledGame, SeatStats,
+    Tournament, TournamentConfig, TournamentError, TournamentReport,
+};
+#[cfg(feature = "tui")]
+pub use tui::{run_tui, TuiOptions};
+pub use visible_state::{VisiblePlayer, VisibleState};
+
+use game_cards::GameDeck;
+use space_cards::SpaceDeck;
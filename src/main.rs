@@ -1,29 +1,720 @@
-use crate::actions::Action;
-use crate::game::PlayerReference;
-use crate::player_controller::{PlayerController, RandomPlayerController};
-use crate::space_cards::{SpaceCard, SpaceDeck};
-use game::Game;
-use game_cards::{GameCard, GameDeck};
-use player::Player;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use miette::{IntoDiagnostic, WrapErr};
 use rand::{thread_rng, Rng};
+use selfish::{
+    seat_seed, Analysis, DeckConfig, Game, GameEvent, GameState, HeuristicPlayerController,
+    IsmctsConfig, IsmctsPlayerController, MisconductPolicy, Penalty, Personality, PlayerController,
+    PlayerReference, RandomPlayerController, Recorder, Replay, RuleSet, SeatedObserver,
+    TerminalObserver, TerminalPlayerController, Tournament, TournamentConfig, BASE_DECKS,
+};
+#[cfg(feature = "fuzz")]
+use selfish::{FuzzConfig, Fuzzer};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
-mod actions;
-mod errors;
-mod game;
-mod game_cards;
-mod player;
-mod player_controller;
-mod space_cards;
-mod visible_state;
+/// Simulate, watch and play Selfish: Space Edition.
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play games between bots and report who won.
+    Simulate(SimulateArgs),
+    /// Check a recorded game still plays out the same, and print what happened.
+    Replay(ReplayArgs),
+    /// Rate bots against each other over many games in every seating.
+    Tournament(TournamentArgs),
+    /// Take a seat against bots.
+    Play(PlayArgs),
+    /// Gather statistics from recorded games, or from freshly simulated ones.
+    Analyze(AnalyzeArgs),
+    /// Print the base game decks to start custom ones from, or check a deck file.
+    Decks(DecksArgs),
+    /// Print the official rules to start variants from, or check a rules file.
+    Rules(RulesArgs),
+    /// Play games against bots that answer with garbage, checking the engine copes.
+    #[cfg(feature = "fuzz")]
+    Fuzz(FuzzArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// See `Penalty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Misconduct {
+    /// Answer for the bot and carry on.
+    Fallback,
+    /// Answer for the bot, and skip its next action phase.
+    Penalize,
+    /// Take the bot out of the game.
+    Forfeit,
+}
+
+/// How bots that answer with something the rules don't allow are dealt with.
+#[derive(Args, Debug)]
+struct MisconductArgs {
+    /// Times to ask a bot again after an invalid answer.
+    #[arg(long, default_value_t = 0)]
+    retries: usize,
+
+    /// What happens once a bot is out of retries.
+    #[arg(long, value_enum, default_value_t = Misconduct::Fallback)]
+    misconduct: Misconduct,
+}
+
+impl MisconductArgs {
+    fn policy(&self) -> MisconductPolicy {
+        MisconductPolicy {
+            retries: self.retries,
+            penalty: match self.misconduct {
+                Misconduct::Fallback => Penalty::Fallback,
+                Misconduct::Penalize => Penalty::Penalize,
+                Misconduct::Forfeit => Penalty::Forfeit,
+            },
+        }
+    }
+}
+
+/// Which rules and decks to play with.
+#[derive(Args, Debug)]
+struct VariantArgs {
+    /// A TOML file of rule changes, see `selfish rules`. The official rules if left out.
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// A TOML file describing the decks, see `selfish decks`. Replaces any decks in the rules.
+    #[arg(long)]
+    decks: Option<PathBuf>,
+}
+
+impl VariantArgs {
+    /// The official rules unless a file changes them, with the decks from their own file if
+    /// given.
+    fn load(&self) -> miette::Result<RuleSet> {
+        let mut rule_set = match &self.rules {
+            None => RuleSet::default(),
+            Some(path) => RuleSet::load(path)?,
+        };
+        if let Some(path) = &self.decks {
+            rule_set.decks = DeckConfig::load(path)?;
+        }
+        Ok(rule_set)
+    }
+}
+
+/// How hard the ismcts bots search.
+#[derive(Args, Debug)]
+struct IsmctsArgs {
+    /// Simulations per decision for ismcts bots.
+    #[arg(long = "ismcts-iterations", default_value_t = 300)]
+    iterations: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Bot {
+    Random,
+    Heuristic,
+    Aggressive,
+    Cautious,
+    Ismcts,
+}
+
+impl Bot {
+    fn name(&self) -> &'static str {
+        match self {
+            Bot::Random => "random",
+            Bot::Heuristic => "heuristic",
+            Bot::Aggressive => "aggressive",
+            Bot::Cautious => "cautious",
+            Bot::Ismcts => "ismcts",
+        }
+    }
+
+    fn controller(&self, seed: u64, ismcts_iterations: usize) -> Box<dyn PlayerController> {
+        match self {
+            Bot::Random => Box::new(RandomPlayerController::with_seed(seed)),
+            Bot::Heuristic => Box::new(HeuristicPlayerController::new(Personality::balanced())),
+            Bot::Aggressive => Box::new(HeuristicPlayerController::new(Personality::aggressive())),
+            Bot::Cautious => Box::new(HeuristicPlayerController::new(Personality::cautious())),
+            Bot::Ismcts => {
+                let config = IsmctsConfig {
+                    iterations: ismcts_iterations,
+                    ..IsmctsConfig::default()
+                };
+                Box::new(IsmctsPlayerController::with_seed(config, seed))
+            }
+        }
+    }
+}
+
+/// Which games to play.
+#[derive(Args, Debug)]
+struct Setup {
+    /// Players at the table.
+    #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(2..=6))]
+    players: u8,
+
+    /// Seed of the first game. Game N is played with this plus N, so any one of them can be
+    /// played again on its own. Random if left out.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Games to play.
+    #[arg(short, long, default_value_t = 1)]
+    games: u64,
+
+    /// The bot in each seat. When there are fewer bots than seats the list is repeated.
+    #[arg(short, long, value_delimiter = ',', default_value = "random")]
+    controllers: Vec<Bot>,
+
+    #[command(flatten)]
+    ismcts: IsmctsArgs,
+
+    /// Check the engine's invariants after every decision, stopping at the first broken one.
+    #[arg(long)]
+    check_invariants: bool,
+
+    #[command(flatten)]
+    misconduct: MisconductArgs,
+
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl Setup {
+    fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| thread_rng().gen())
+    }
+
+    fn bots(&self) -> Vec<Bot> {
+        self.controllers
+            .iter()
+            .cycle()
+            .take(self.players as usize)
+            .copied()
+            .collect()
+    }
+
+    /// A game between the bots, along with a recorder listening to it.
+    fn game(&self, seed: u64, rules: &RuleSet) -> miette::Result<(Game, Recorder)> {
+        let controllers = self
+            .bots()
+            .iter()
+            .enumerate()
+            .map(|(seat, bot)| bot.controller(seat_seed(seed, seat), self.ismcts.iterations))
+            .collect();
+        let state = GameState::with_rules(Some(seed), self.players as usize, rules.clone())?;
+        let recorder = Recorder::new();
+        let mut game = Game::from_state(state, recorder.wrap_all(controllers));
+        game.set_check_invariants(self.check_invariants);
+        game.set_misconduct_policy(self.misconduct.policy());
+        recorder.attach(&mut game);
+        Ok((game, recorder))
+    }
+}
+
+#[derive(Args, Debug)]
+struct SimulateArgs {
+    #[command(flatten)]
+    setup: Setup,
+
+    /// Save a replay of every game into this directory, named after its seed.
+    #[arg(short, long)]
+    record: Option<PathBuf>,
+
+    /// Print the board as each game is played.
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Watch each game full screen, with controls to pause and change the speed.
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with = "watch")]
+    tui: bool,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Args, Debug)]
+struct ReplayArgs {
+    /// A replay saved by `simulate --record`.
+    file: PathBuf,
+
+    /// Text prints a line per event, JSON prints an event per line.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Args, Debug)]
+struct TournamentArgs {
+    /// The bots taking part.
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "random,heuristic,ismcts"
+    )]
+    entrants: Vec<Bot>,
+
+    /// Players at each table.
+    #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(2..=6))]
+    players: u8,
+
+    /// Deals to play, each in every seating of its lineup.
+    #[arg(short, long, default_value_t = 20)]
+    rounds: usize,
+
+    /// Random if left out.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Games to play at once. Defaults to the number of CPU cores.
+    #[arg(short, long)]
+    threads: Option<usize>,
+
+    #[command(flatten)]
+    ismcts: IsmctsArgs,
+
+    #[command(flatten)]
+    misconduct: MisconductArgs,
+
+    #[command(flatten)]
+    variant: VariantArgs,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Args, Debug)]
+struct PlayArgs {
+    /// Players at the table, including you.
+    #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(2..=6))]
+    players: u8,
+
+    /// Your seat, counting from 0. Seat 0 goes first.
+    #[arg(long, default_value_t = 0)]
+    seat: usize,
+
+    /// Random if left out.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// The bots in the other seats, repeated to fill the table.
+    #[arg(short, long, value_delimiter = ',', default_value = "heuristic")]
+    opponents: Vec<Bot>,
+
+    #[command(flatten)]
+    ismcts: IsmctsArgs,
+
+    #[command(flatten)]
+    variant: VariantArgs,
+
+    /// Play full screen instead of answering numbered menus.
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    tui: bool,
+}
+
+#[derive(Args, Debug)]
+struct AnalyzeArgs {
+    /// Replays to analyze. When none are given, games are simulated instead.
+    files: Vec<PathBuf>,
+
+    #[command(flatten)]
+    setup: Setup,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Args, Debug)]
+struct DecksArgs {
+    /// A deck file to check instead.
+    file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct RulesArgs {
+    /// A rules file to check instead.
+    file: Option<PathBuf>,
+}
+
+#[cfg(feature = "fuzz")]
+#[derive(Args, Debug)]
+struct FuzzArgs {
+    /// Games to play.
+    #[arg(short, long, default_value_t = 10_000)]
+    games: usize,
+
+    /// Seed of the first game. Game N is played with this plus N.
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+
+    /// Players at each table, taking turns from game to game.
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "2,3,4,5,6",
+        value_parser = clap::value_parser!(u8).range(2..=6)
+    )]
+    players: Vec<u8>,
+
+    /// The chance of each answer being garbage, from 0 to 1.
+    #[arg(long, default_value_t = 0.3)]
+    chaos: f64,
+
+    #[command(flatten)]
+    variant: VariantArgs,
+}
 
 fn main() -> miette::Result<()> {
-    let mut controllers: Vec<Box<dyn PlayerController>> = Vec::new();
-    for _ in 0..4 {
-        controllers.push(Box::new(RandomPlayerController::new()));
+    match Cli::parse().command {
+        Command::Simulate(args) => simulate(args),
+        Command::Replay(args) => replay(args),
+        Command::Tournament(args) => tournament(args),
+        Command::Play(args) => play(args),
+        Command::Analyze(args) => analyze(args),
+        Command::Decks(args) => decks(args),
+        Command::Rules(args) => rules(args),
+        #[cfg(feature = "fuzz")]
+        Command::Fuzz(args) => fuzz(args),
+    }
+}
+
+/// How one simulated game went.
+#[derive(Serialize)]
+struct GameSummary {
+    seed: u64,
+    controllers: Vec<Bot>,
+    winner: Option<usize>,
+    turns: usize,
+}
+
+#[derive(Serialize)]
+struct SimulateOutput {
+    games: Vec<GameSummary>,
+    wins: BTreeMap<&'static str, usize>,
+}
+
+fn simulate(args: SimulateArgs) -> miette::Result<()> {
+    if let Some(dir) = &args.record {
+        std::fs::create_dir_all(dir)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Creating {}", dir.display()))?;
+    }
+
+    let first_seed = args.setup.seed();
+    let bots = args.setup.bots();
+    let rules = args.setup.variant.load()?;
+    let mut output = SimulateOutput {
+        games: Vec::new(),
+        wins: BTreeMap::new(),
+    };
+    for idx in 0..args.setup.games {
+        let seed = first_seed.wrapping_add(idx);
+        let (mut game, recorder) = args.setup.game(seed, &rules)?;
+        if args.watch {
+            game.add_observer(Box::new(TerminalObserver::new()));
+        }
+        #[cfg(feature = "tui")]
+        if args.tui {
+            selfish::run_tui(&mut game, selfish::TuiOptions::default())?;
+            if game.winner().is_none() {
+                // Quit before the end.
+                break;
+            }
+        }
+        game.simulate()?;
+
+        let replay = recorder.finish(&game);
+        if let Some(dir) = &args.record {
+            replay.save(dir.join(format!("{}.json", seed)))?;
+        }
+
+        let summary = GameSummary {
+            seed,
+            controllers: bots.clone(),
+            winner: game.winner().map(|winner| winner.0),
+            turns: count_turns(&replay.events),
+        };
+        if let Some(winner) = summary.winner {
+            *output.wins.entry(bots[winner].name()).or_default() += 1;
+        }
+        if args.format == Format::Text {
+            match summary.winner {
+                Some(winner) => println!(
+                    "Seed {}: player {} ({}) won after {} turns.",
+                    seed,
+                    winner,
+                    bots[winner].name(),
+                    summary.turns
+                ),
+                None => println!("Seed {}: nobody won.", seed),
+            }
+        }
+        output.games.push(summary);
+    }
+
+    match args.format {
+        Format::Text if output.games.len() > 1 => {
+            println!();
+            for (bot, wins) in &output.wins {
+                println!("{:<12} {} wins", bot, wins);
+            }
+        }
+        Format::Text => {}
+        Format::Json => print_json(&output)?,
+    }
+    Ok(())
+}
+
+fn replay(args: ReplayArgs) -> miette::Result<()> {
+    let replay = Replay::load(&args.file)?;
+    let game = replay
+        .verify()
+        .wrap_err_with(|| format!("Replaying {}", args.file.display()))?;
+
+    match args.format {
+        Format::Text => {
+            for event in &replay.events {
+                println!("{}", event);
+            }
+        }
+        Format::Json => {
+            for event in &replay.events {
+                println!("{}", serde_json::to_string(event).into_diagnostic()?);
+            }
+        }
+    }
+    if args.format == Format::Text {
+        if let Some(winner) = game.winner() {
+            println!("Replay verified, player {} won.", winner.0);
+        }
+    }
+    Ok(())
+}
+
+fn tournament(args: TournamentArgs) -> miette::Result<()> {
+    let mut config = TournamentConfig {
+        players_per_game: args.players as usize,
+        rounds: args.rounds,
+        seed: args.seed.unwrap_or_else(|| thread_rng().gen()),
+        rules: args.variant.load()?,
+        misconduct_policy: args.misconduct.policy(),
+        ..TournamentConfig::default()
+    };
+    if let Some(threads) = args.threads {
+        config.threads = threads;
+    }
+
+    let mut tournament = Tournament::new(config);
+    for bot in args.entrants {
+        let iterations = args.ismcts.iterations;
+        tournament.add_entrant(bot.name(), move |seed| bot.controller(seed, iterations));
+    }
+
+    let report = tournament.run()?;
+    match args.format {
+        Format::Text => println!("{}", report),
+        Format::Json => print_json(&report)?,
+    }
+    Ok(())
+}
+
+fn play(args: PlayArgs) -> miette::Result<()> {
+    let players = args.players as usize;
+    if args.seat >= players {
+        miette::bail!(
+            "There is no seat {} at a table of {}, pick one from 0 to {}.",
+            args.seat,
+            players,
+            players - 1
+        );
+    }
+
+    let seed = args.seed.unwrap_or_else(|| thread_rng().gen());
+    let state = GameState::with_rules(Some(seed), players, args.variant.load()?)?;
+    let mut opponents = args.opponents.iter().cycle();
+    let mut bots = Vec::new();
+    for seat in 0..players {
+        if seat == args.seat {
+            bots.push(None);
+        } else {
+            let bot = opponents.next().unwrap();
+            bots.push(Some(
+                bot.controller(seat_seed(seed, seat), args.ismcts.iterations),
+            ));
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    if args.tui {
+        let mut game = Game::from_state_with_seats(state, bots);
+        let options = selfish::TuiOptions {
+            human: Some(PlayerReference(args.seat)),
+            ..selfish::TuiOptions::default()
+        };
+        selfish::run_tui(&mut game, options)?;
+        print_winner(&game);
+        return Ok(());
     }
-    let seed = Some(thread_rng().gen());
-    let mut game = Game::new(seed, controllers);
+
+    let controllers = bots
+        .into_iter()
+        .map(|bot| bot.unwrap_or_else(|| Box::new(TerminalPlayerController::stdio())))
+        .collect();
+    let mut game = Game::from_state(state, controllers);
+    game.add_observer(Box::new(SeatedObserver::new(
+        PlayerReference(args.seat),
+        TerminalObserver::events_only(),
+    )));
     game.simulate()?;
+    print_winner(&game);
+    Ok(())
+}
+
+fn analyze(args: AnalyzeArgs) -> miette::Result<()> {
+    let mut analysis = Analysis::new();
+    if args.files.is_empty() {
+        let first_seed = args.setup.seed();
+        let rules = args.setup.variant.load()?;
+        for idx in 0..args.setup.games {
+            let (mut game, recorder) = args.setup.game(first_seed.wrapping_add(idx), &rules)?;
+            game.simulate()?;
+            analysis.add_game(&recorder.finish(&game).events);
+        }
+    } else {
+        for file in &args.files {
+            analysis.add_game(&Replay::load(file)?.events);
+        }
+    }
+
+    match args.format {
+        Format::Text => print!("{}", analysis),
+        Format::Json => print_json(&analysis)?,
+    }
+    Ok(())
+}
+
+fn decks(args: DecksArgs) -> miette::Result<()> {
+    let Some(file) = args.file else {
+        print!("{}", BASE_DECKS);
+        return Ok(());
+    };
+
+    // Decks are checked against the official starting hands.
+    let rules = RuleSet {
+        decks: DeckConfig::load(&file)?,
+        ..RuleSet::default()
+    };
+    println!(
+        "{} game cards and {} space cards.",
+        rules.decks.game_deck().len(),
+        rules.decks.space_deck().len()
+    );
+    print_tables(&rules)
+}
 
+fn rules(args: RulesArgs) -> miette::Result<()> {
+    let Some(file) = args.file else {
+        print!("{}", RuleSet::default().to_toml());
+        return Ok(());
+    };
+
+    let rules = RuleSet::load(&file)?;
+    if rules == RuleSet::default() {
+        println!("These are the official rules.");
+    }
+    print_tables(&rules)
+}
+
+#[cfg(feature = "fuzz")]
+fn fuzz(args: FuzzArgs) -> miette::Result<()> {
+    let fuzzer = Fuzzer::new(FuzzConfig {
+        games: args.games,
+        seed: args.seed,
+        player_counts: args.players.iter().map(|n| *n as usize).collect(),
+        chaos: args.chaos,
+        rules: args.variant.load()?,
+        ..FuzzConfig::default()
+    });
+    let report = fuzzer.run()?;
+    println!("{}", report);
     Ok(())
 }
+
+/// Say how many players the rules can be played with, or why not even two can.
+fn print_tables(rules: &RuleSet) -> miette::Result<()> {
+    let tables: Vec<usize> = (2..=6)
+        .filter(|player_count| rules.check(*player_count).is_ok())
+        .collect();
+    match (tables.first(), tables.last()) {
+        (Some(min), Some(max)) => println!("Enough to play with {} to {} players.", min, max),
+        _ => rules.check(2)?,
+    }
+    Ok(())
+}
+
+fn print_json(value: &impl Serialize) -> miette::Result<()> {
+    let json = serde_json::to_string_pretty(value).into_diagnostic()?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn print_winner(game: &Game) {
+    if let Some(winner) = game.winner() {
+        println!("Player {} won!", winner.0);
+    }
+}
+
+fn count_turns(events: &[GameEvent]) -> usize {
+    events
+        .iter()
+        .filter(|event| matches!(event, GameEvent::TurnStarted { .. }))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_bots_fill_the_table() {
+        let cli = Cli::parse_from(["selfish", "simulate", "-p", "5", "-c", "random,ismcts"]);
+        let Command::Simulate(args) = cli.command else {
+            panic!("expected simulate");
+        };
+        assert_eq!(
+            args.setup.bots(),
+            vec![
+                Bot::Random,
+                Bot::Ismcts,
+                Bot::Random,
+                Bot::Ismcts,
+                Bot::Random
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_player_count() {
+        assert!(Cli::try_parse_from(["selfish", "simulate", "-p", "7"]).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{Decision, GameState, PendingDecision};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// What `Game::step` does when a controller answers with something the rules don't allow.
+///
+/// Every rejected answer is reported as a `GameEvent::DecisionRejected`. The controller is asked
+/// again up to `retries` times, and after that the `penalty` decides what happens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MisconductPolicy {
+    /// How many more times the controller is asked after its first answer is rejected.
+    pub retries: usize,
+
+    pub penalty: Penalty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Penalty {
+    /// Answer for the controller with `fallback_decision`, and carry on.
+    #[default]
+    Fallback,
+
+    /// Answer for the controller, and skip the player's next action phase.
+    Penalize,
+
+    /// The player is out of the game, as if they had died. Until that can happen, between
+    /// actions, they are answered for without being asked.
+    Forfeit,
+}
+
+impl Display for Penalty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Penalty::Fallback => "fallback",
+            Penalty::Penalize => "penalize",
+            Penalty::Forfeit => "forfeit",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// The answer that changes the least for a player who didn't give a valid one: no action, no
+/// shield, breathing if they can, and otherwise the first legal choice.
+///
+/// None if nothing is pending.
+pub fn fallback_decision(state: &GameState) -> Option<Decision> {
+    match state.pending_decision()? {
+        PendingDecision::PlayAction { .. } => Some(Decision::PlayAction(None)),
+        PendingDecision::Defend { .. } => Some(Decision::Defend(false)),
+        _ => state.legal_decisions().into_iter().next(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Action, BreatheOrTravel, DeathCause, Game, GameCard, GameEvent, Phase, PlayerController,
+        PlayerReference, RandomPlayerController, Scenario, SeatedObserver, VisibleState,
+    };
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    /// Always tries to laser blast itself, and otherwise plays randomly.
+    struct Cheater(RandomPlayerController);
+
+    impl PlayerController for Cheater {
+        fn update_state(&mut self, visible_state: VisibleState) {
+            self.0.update_state(visible_state);
+        }
+
+        fn play_action(&mut self) -> Option<Action> {
+            Some(Action::LaserBlast {
+                target: PlayerReference(0),
+            })
+        }
+
+        fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+            self.0.breathe_or_travel()
+        }
+
+        fn defend(&mut self, action: &Action) -> bool {
+            self.0.defend(action)
+        }
+
+        fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+            self.0.forced_discard(card_count)
+        }
+
+        fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+            self.0.choose_player_to_swap_with()
+        }
+
+        fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+            self.0.choose_card_to_take(options)
+        }
+    }
+
+    /// Hacks player 1's suit, then asks for a card they don't have.
+    struct Thief(RandomPlayerController);
+
+    impl PlayerController for Thief {
+        fn update_state(&mut self, visible_state: VisibleState) {
+            self.0.update_state(visible_state);
+        }
+
+        fn play_action(&mut self) -> Option<Action> {
+            Some(Action::HackSuit {
+                target: PlayerReference(1),
+            })
+        }
+
+        fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+            self.0.breathe_or_travel()
+        }
+
+        fn defend(&mut self, action: &Action) -> bool {
+            self.0.defend(action)
+        }
+
+        fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+            self.0.forced_discard(card_count)
+        }
+
+        fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+            self.0.choose_player_to_swap_with()
+        }
+
+        fn choose_card_to_take(&mut self, _options: HashSet<GameCard>) -> GameCard {
+            GameCard::Shield
+        }
+    }
+
+    fn game(policy: MisconductPolicy) -> (Game, Rc<RefCell<Vec<GameEvent>>>) {
+        let state = Scenario::new(3)
+            .hand(0, [GameCard::O2, GameCard::O1, GameCard::LaserBlast])
+            .phase(Phase::Actions)
+            .build()
+            .unwrap();
+        let controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(Cheater(RandomPlayerController::with_seed(0))),
+            Box::new(RandomPlayerController::with_seed(1)),
+            Box::new(RandomPlayerController::with_seed(2)),
+        ];
+        let mut game = Game::from_state(state, controllers);
+        game.set_misconduct_policy(policy);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let recorded = events.clone();
+        game.add_observer(Box::new(move |_: &Game, event: &GameEvent| {
+            recorded.borrow_mut().push(event.clone());
+        }));
+        game.start().unwrap();
+        (game, events)
+    }
+
+    fn rejections(events: &[GameEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| matches!(event, GameEvent::DecisionRejected { .. }))
+            .count()
+    }
+
+    #[test]
+    fn test_retry_then_fall_back() {
+        let (mut game, events) = game(MisconductPolicy {
+            retries: 2,
+            penalty: Penalty::Fallback,
+        });
+        game.step().unwrap();
+        let events = events.borrow();
+        assert_eq!(rejections(&events), 3);
+        assert!(events.contains(&GameEvent::DecisionImposed {
+            player: PlayerReference(0),
+            decision: Decision::PlayAction(None),
+        }));
+        assert!(game.player(&PlayerReference(0)).unwrap().alive);
+    }
+
+    #[test]
+    fn test_penalize_skips_next_actions() {
+        let (mut game, events) = game(MisconductPolicy {
+            retries: 0,
+            penalty: Penalty::Penalize,
+        });
+        while game.whose_turn() == PlayerReference(0) {
+            game.step().unwrap();
+        }
+        assert_eq!(rejections(&events.borrow()), 1);
+        while game.whose_turn() != PlayerReference(0) {
+            game.step().unwrap();
+        }
+        game.step().unwrap();
+        let events = events.borrow();
+        assert_eq!(rejections(&events), 1);
+        assert!(events.contains(&GameEvent::ActionsSkipped {
+            player: PlayerReference(0)
+        }));
+    }
+
+    #[test]
+    fn test_forfeit() {
+        let (mut game, events) = game(MisconductPolicy {
+            retries: 0,
+            penalty: Penalty::Forfeit,
+        });
+        game.step().unwrap();
+        assert!(!game.player(&PlayerReference(0)).unwrap().alive);
+        assert!(events.borrow().contains(&GameEvent::PlayerDied {
+            player: PlayerReference(0),
+            cause: DeathCause::Forfeited,
+        }));
+        game.simulate().unwrap();
+        assert_ne!(game.winner(), Some(PlayerReference(0)));
+    }
+
+    #[test]
+    fn test_imposed_card_is_private() {
+        let state = Scenario::new(3)
+            .hand(0, [GameCard::O2, GameCard::HackSuit])
+            .hand(1, [GameCard::O1, GameCard::O2])
+            .phase(Phase::Actions)
+            .build()
+            .unwrap();
+        let controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(Thief(RandomPlayerController::with_seed(0))),
+            Box::new(RandomPlayerController::with_seed(1)),
+            Box::new(RandomPlayerController::with_seed(2)),
+        ];
+        let mut game = Game::from_state(state, controllers);
+        let mut logs = Vec::new();
+        for seat in (0..3).map(PlayerReference) {
+            let log = Rc::new(RefCell::new(Vec::new()));
+            let recorded = log.clone();
+            let record = move |_: &Game, event: &GameEvent| {
+                recorded.borrow_mut().push(event.clone());
+            };
+            game.add_observer(Box::new(SeatedObserver::new(seat, record)));
+            logs.push(log);
+        }
+        game.start().unwrap();
+        game.step().unwrap();
+        game.step().unwrap();
+
+        let imposed = GameEvent::DecisionImposed {
+            player: PlayerReference(0),
+            decision: Decision::TakeCard(GameCard::O1),
+        };
+        for log in &logs[..2] {
+            let log = log.borrow();
+            assert_eq!(rejections(&log), 1);
+            assert!(log.contains(&imposed));
+        }
+        let log = logs[2].borrow();
+        assert!(!log.iter().any(|event| matches!(
+            event,
+            GameEvent::DecisionRejected { .. } | GameEvent::DecisionImposed { .. }
+        )));
+    }
+}
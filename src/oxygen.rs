@@ -0,0 +1,123 @@
+use crate::{GameCard, GameDeck};
+use serde::{Deserialize, Serialize};
+
+/// Where the O1s come from when a player pays more oxygen than they owe, e.g. an O2 for a hole in
+/// their suit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OxygenChange {
+    /// From the discard pile, then from the deck if there aren't enough there. The official rule.
+    #[default]
+    DiscardPile,
+
+    /// From the deck, then from the discard pile.
+    Deck,
+
+    /// There is no change, so overpaying loses the difference.
+    None,
+}
+
+/// How much oxygen the card is worth, which is nothing for anything but O1 and O2.
+pub fn oxygen_value(card: &GameCard) -> usize {
+    match card {
+        GameCard::O1 => 1,
+        GameCard::O2 => 2,
+        _ => 0,
+    }
+}
+
+/// Every way to pay `cost` oxygen out of the hand without handing over a card that isn't needed,
+/// fewest O2s first. Empty if the hand doesn't hold enough oxygen.
+pub fn payments(hand: &[GameCard], cost: usize) -> Vec<Vec<GameCard>> {
+    let ones = hand.iter().filter(|card| **card == GameCard::O1).count();
+    let twos = hand.iter().filter(|card| **card == GameCard::O2).count();
+
+    let mut options = Vec::new();
+    for o2s in 0..=twos {
+        for o1s in 0..=ones {
+            let paid = o1s + 2 * o2s;
+            let needs_o1s = o1s == 0 || paid - 1 < cost;
+            let needs_o2s = o2s == 0 || paid - 2 < cost;
+            if paid >= cost && needs_o1s && needs_o2s {
+                options.push(
+                    std::iter::repeat_n(GameCard::O1, o1s)
+                        .chain(std::iter::repeat_n(GameCard::O2, o2s))
+                        .collect(),
+                );
+            }
+        }
+    }
+    options
+}
+
+/// Take up to `count` O1s out of the deck's piles as change, from where the rules say. There may
+/// not be enough.
+pub(crate) fn take_change(
+    deck: &mut GameDeck,
+    count: usize,
+    change: OxygenChange,
+) -> Vec<GameCard> {
+    let mut taken = Vec::new();
+    let from_discard_first = match change {
+        OxygenChange::DiscardPile => true,
+        OxygenChange::Deck => false,
+        OxygenChange::None => return taken,
+    };
+    for from_discard in [from_discard_first, !from_discard_first] {
+        while taken.len() < count {
+            let found = if from_discard {
+                deck.remove_from_discard(&GameCard::O1)
+            } else {
+                deck.remove_from_available(&GameCard::O1)
+            };
+            if !found {
+                break;
+            }
+            taken.push(GameCard::O1);
+        }
+    }
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use GameCard::{Shield, O1, O2};
+
+    #[test]
+    fn test_payments() {
+        assert_eq!(payments(&[O1, O2, Shield], 1), vec![vec![O1], vec![O2]]);
+        assert_eq!(payments(&[O1, O2], 2), vec![vec![O2]]);
+        assert_eq!(payments(&[O1, O1, O1, O2], 2), vec![vec![O1, O1], vec![O2]]);
+        assert_eq!(
+            payments(&[O1, O1, O2, O2], 3),
+            vec![vec![O1, O2], vec![O2, O2]]
+        );
+        assert_eq!(payments(&[O2, O2], 2), vec![vec![O2]]);
+        assert_eq!(payments(&[O1, Shield], 2), Vec::<Vec<GameCard>>::new());
+    }
+
+    #[test]
+    fn test_take_change() {
+        let deck = GameDeck::from_parts(vec![O1, Shield], vec![O1, O2]);
+
+        let mut from_discard = deck.clone();
+        assert_eq!(
+            take_change(&mut from_discard, 1, OxygenChange::DiscardPile),
+            vec![O1]
+        );
+        assert_eq!(from_discard.discard_pile(), &[O2]);
+        assert_eq!(from_discard.available(), &[O1, Shield]);
+
+        let mut from_deck = deck.clone();
+        assert_eq!(
+            take_change(&mut from_deck, 3, OxygenChange::Deck),
+            vec![O1, O1]
+        );
+        assert_eq!(from_deck.discard_pile(), &[O2]);
+        assert_eq!(from_deck.available(), &[Shield]);
+
+        let mut none = deck.clone();
+        assert_eq!(take_change(&mut none, 1, OxygenChange::None), vec![]);
+    }
+}
@@ -4,7 +4,7 @@ use miette::bail;
 use rand::Rng;
 use std::collections::HashSet;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Player {
     pub alive: bool,
     pub hand: Vec<GameCard>,
@@ -14,7 +14,7 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn new() -> Player {
+    pub(crate) fn new() -> Player {
         Player {
             alive: true,
             hand: Vec::new(),
@@ -22,7 +22,7 @@ impl Player {
         }
     }
 
-    pub fn give(&mut self, card: GameCard) {
+    pub(crate) fn give(&mut self, card: GameCard) {
         self.hand.push(card);
     }
 
@@ -39,7 +39,7 @@ impl Player {
     }
 
     /// Remove a card from the player's hand.
-    pub fn remove_card(&mut self, card: &GameCard) -> miette::Result<()> {
+    pub(crate) fn remove_card(&mut self, card: &GameCard) -> miette::Result<()> {
         let index = self
             .hand
             .iter()
@@ -52,8 +52,8 @@ impl Player {
     }
 
     /// Will return an error if the player does not have any cards.
-    pub fn remove_random_card(&mut self, rng: &mut impl Rng) -> miette::Result<GameCard> {
-        if self.hand.len() == 0 {
+    pub(crate) fn remove_random_card(&mut self, rng: &mut impl Rng) -> miette::Result<GameCard> {
+        if self.hand.is_empty() {
             bail!(SelfishError::PlayerHasNoCardsLeft);
         }
 
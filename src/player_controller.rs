@@ -1,5 +1,6 @@
 use crate::actions::BreatheOrTravel;
-use crate::visible_state::{VisiblePlayer, VisibleState};
+use crate::decision::{Decision, PendingDecision};
+use crate::visible_state::VisibleState;
 use crate::{Action, GameCard, PlayerReference};
 use rand::prelude::SliceRandom;
 use rand::{Rng, SeedableRng};
@@ -29,6 +30,38 @@ pub trait PlayerController {
 
     /// Hack suit to choose a card to steal.
     fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard;
+
+    /// Only asked when there is more than one way to pay oxygen, with the options fewest O2s
+    /// first. By default the first, which keeps the O2s for travelling.
+    fn pay_oxygen(&mut self, _cost: usize, options: Vec<Vec<GameCard>>) -> Vec<GameCard> {
+        options.into_iter().next().unwrap_or_default()
+    }
+
+    /// Answer whatever the game is waiting on by asking the matching question above.
+    ///
+    /// This is how `Game::step` drives a controller, so a controller that would rather look at
+    /// the whole `PendingDecision` can override it.
+    fn decide(&mut self, pending: &PendingDecision) -> Decision {
+        match pending {
+            PendingDecision::PlayAction { .. } => Decision::PlayAction(self.play_action()),
+            PendingDecision::Defend { action, .. } => Decision::Defend(self.defend(action)),
+            PendingDecision::ChooseCardToTake { options, .. } => {
+                Decision::TakeCard(self.choose_card_to_take(options.iter().copied().collect()))
+            }
+            PendingDecision::BreatheOrTravel { .. } => {
+                Decision::BreatheOrTravel(self.breathe_or_travel())
+            }
+            PendingDecision::ForcedDiscard { card_count, .. } => {
+                Decision::ForcedDiscard(self.forced_discard(*card_count))
+            }
+            PendingDecision::ChooseSwapTarget { .. } => {
+                Decision::SwapWith(self.choose_player_to_swap_with())
+            }
+            PendingDecision::PayOxygen { cost, options, .. } => {
+                Decision::PayOxygen(self.pay_oxygen(*cost, options.clone()))
+            }
+        }
+    }
 }
 
 pub struct RandomPlayerController {
@@ -43,6 +76,20 @@ impl RandomPlayerController {
             visible_state: VisibleState::invalid(),
         }
     }
+
+    /// Makes the same choices every time, given the same game.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            visible_state: VisibleState::invalid(),
+        }
+    }
+}
+
+impl Default for RandomPlayerController {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PlayerController for RandomPlayerController {
@@ -51,10 +98,19 @@ impl PlayerController for RandomPlayerController {
     }
 
     fn play_action(&mut self) -> Option<Action> {
-        random_action(&self.visible_state)
+        let actions = self.visible_state.legal_actions();
+        actions.choose(&mut self.rng).cloned()
     }
 
     fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        // Don't wander past a guaranteed win.
+        let state = &self.visible_state;
+        if let Some(me) = state.players.get(state.viewer.0) {
+            if me.space.len() + 1 >= state.ship_distance {
+                return BreatheOrTravel::Travel;
+            }
+        }
+
         if self.rng.gen() {
             BreatheOrTravel::Breathe
         } else {
@@ -67,93 +123,26 @@ impl PlayerController for RandomPlayerController {
     }
 
     fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
-        let mut cards = self.visible_state.my_hand.clone();
-        cards.shuffle(&mut self.rng);
-        cards.truncate(card_count);
-        cards
+        let discards = self.visible_state.legal_discards(card_count);
+        discards.choose(&mut self.rng).cloned().unwrap_or_default()
     }
 
     fn choose_player_to_swap_with(&mut self) -> PlayerReference {
-        let targets = potential_targets(&self.visible_state, false, 0, false);
-        let target = targets.choose(&mut self.rng).unwrap();
-        *target
+        let targets = self.visible_state.legal_swap_targets();
+        targets
+            .choose(&mut self.rng)
+            .copied()
+            .unwrap_or(self.visible_state.viewer)
     }
 
     fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
-        *options.iter().next().unwrap()
+        // Sorted first, since the order of a set changes from run to run.
+        let mut options: Vec<GameCard> = options.into_iter().collect();
+        options.sort_by_key(|card| *card as usize);
+        *options.choose(&mut self.rng).unwrap()
     }
-}
-
-fn potential_targets(
-    visible_state: &VisibleState,
-    needs_to_be_alive: bool,
-    needs_cards: usize,
-    needs_to_have_space: bool,
-) -> Vec<PlayerReference> {
-    let mut found = Vec::new();
-    for (idx, player) in visible_state.players.iter().enumerate() {
-        let player_reference = PlayerReference(idx);
-
-        if player_reference == visible_state.whose_turn {
-            continue;
-        }
-        if needs_to_be_alive && !player.alive {
-            continue;
-        }
-        if needs_cards > 0 && player.hand_size < needs_cards {
-            continue;
-        }
-        if needs_to_have_space && player.space.is_empty() {
-            continue;
-        }
 
-        found.push(player_reference);
+    fn pay_oxygen(&mut self, _cost: usize, options: Vec<Vec<GameCard>>) -> Vec<GameCard> {
+        options.choose(&mut self.rng).cloned().unwrap_or_default()
     }
-
-    found
-}
-
-fn random_action(visible_state: &VisibleState) -> Option<Action> {
-    let targets = potential_targets(visible_state, true, 1, false);
-    let target = match targets.first() {
-        Some(player_reference) => player_reference,
-        None => return None,
-    }
-    .to_owned();
-
-    for card in &visible_state.my_hand {
-        match card {
-            GameCard::OxygenSiphon => {
-                return Some(Action::OxygenSiphon { target });
-            }
-            GameCard::HackSuit => {
-                return Some(Action::HackSuit { target });
-            }
-            GameCard::TractorBeam => {
-                return Some(Action::TractorBeam { target });
-            }
-            GameCard::RocketBooster => {
-                return Some(Action::RocketBooster);
-            }
-            GameCard::LaserBlast => {
-                let target = *potential_targets(visible_state, true, 1, true).first()?;
-                return Some(Action::LaserBlast { target });
-            }
-            GameCard::HoleInSuit => {
-                return Some(Action::HoleInSuit { target });
-            }
-            GameCard::Tether => {
-                let target = *potential_targets(visible_state, true, 1, true).first()?;
-                return Some(Action::Tether { target });
-            }
-            // Can't use as an action.
-            GameCard::O1 => {}
-            // Can't use as an action.
-            GameCard::O2 => {}
-            // Can't use as an action.
-            GameCard::Shield => {}
-        }
-    }
-
-    None
 }
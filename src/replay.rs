@@ -0,0 +1,485 @@
+use crate::actions::BreatheOrTravel;
+use crate::decision::{Decision, PendingDecision};
+use crate::events::GameEvent;
+use crate::visible_state::VisibleState;
+use crate::{
+    Action, Game, GameCard, GameState, MisconductPolicy, PlayerController, PlayerReference, RuleSet,
+};
+use miette::{Diagnostic, IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+use thiserror::Error;
+
+/// Bumped whenever the file format or the engine changes in a way that breaks old replays.
+pub const REPLAY_VERSION: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedDecision {
+    pub player: PlayerReference,
+    pub decision: Decision,
+}
+
+/// Everything needed to play a game again exactly as it happened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    /// The rules the game was played by, always with the ship distance filled in.
+    pub rules: RuleSet,
+    /// How the game dealt with invalid decisions, which are recorded along with the rest.
+    pub misconduct_policy: MisconductPolicy,
+    pub player_count: usize,
+    /// Every controller decision, in the order the engine asked for them.
+    pub decisions: Vec<RecordedDecision>,
+    /// What happened, used to verify the replay.
+    pub events: Vec<GameEvent>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum ReplayError {
+    #[error("Replay version {found} is not supported, expected version {expected}.")]
+    UnsupportedVersion { found: u32, expected: u32 },
+
+    #[error("The engine asked player {player:?} to {asked} but the replay has {recorded}.")]
+    Desync {
+        player: PlayerReference,
+        asked: &'static str,
+        recorded: String,
+    },
+
+    #[error("Not every recorded decision was used, {remaining} are left over.")]
+    UnusedDecisions { remaining: usize },
+
+    #[error("Event {index} differs: recorded {recorded} but replayed {replayed}.")]
+    EventMismatch {
+        index: usize,
+        recorded: String,
+        replayed: String,
+    },
+}
+
+/// Captures the decisions and events of a game while it is played.
+///
+/// ```
+/// use selfish::{Game, PlayerController, RandomPlayerController, Recorder};
+///
+/// let recorder = Recorder::new();
+/// let controllers: Vec<Box<dyn PlayerController>> = (0..3)
+///     .map(|_| Box::new(RandomPlayerController::new()) as Box<dyn PlayerController>)
+///     .collect();
+/// let mut game = Game::new(None, recorder.wrap_all(controllers));
+/// recorder.attach(&mut game);
+/// game.simulate().unwrap();
+///
+/// let replay = recorder.finish(&game);
+/// replay.verify().unwrap();
+/// ```
+#[derive(Default)]
+pub struct Recorder {
+    decisions: Rc<RefCell<Vec<RecordedDecision>>>,
+    events: Rc<RefCell<Vec<GameEvent>>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record every decision the controller makes for the player's seat.
+    pub fn wrap(
+        &self,
+        player: PlayerReference,
+        controller: Box<dyn PlayerController>,
+    ) -> Box<dyn PlayerController> {
+        Box::new(RecordingController {
+            player,
+            inner: controller,
+            decisions: self.decisions.clone(),
+        })
+    }
+
+    /// Wrap the controllers of every seat, in seat order.
+    pub fn wrap_all(
+        &self,
+        controllers: Vec<Box<dyn PlayerController>>,
+    ) -> Vec<Box<dyn PlayerController>> {
+        controllers
+            .into_iter()
+            .enumerate()
+            .map(|(idx, controller)| self.wrap(PlayerReference(idx), controller))
+            .collect()
+    }
+
+    /// Start recording the game's events. Call this before the game is simulated.
+    pub fn attach(&self, game: &mut Game) {
+        let events = self.events.clone();
+        game.add_observer(Box::new(move |_: &Game, event: &GameEvent| {
+            events.borrow_mut().push(event.clone());
+        }));
+    }
+
+    pub fn finish(&self, game: &Game) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed: game.seed(),
+            rules: RuleSet {
+                ship_distance: Some(game.ship_distance()),
+                ..game.rules().clone()
+            },
+            misconduct_policy: game.misconduct_policy(),
+            player_count: game.player_count(),
+            decisions: self.decisions.borrow().clone(),
+            events: self.events.borrow().clone(),
+        }
+    }
+}
+
+struct RecordingController {
+    player: PlayerReference,
+    inner: Box<dyn PlayerController>,
+    decisions: Rc<RefCell<Vec<RecordedDecision>>>,
+}
+
+impl RecordingController {
+    fn record(&self, decision: Decision) {
+        self.decisions.borrow_mut().push(RecordedDecision {
+            player: self.player,
+            decision,
+        });
+    }
+}
+
+impl PlayerController for RecordingController {
+    fn update_state(&mut self, visible_state: VisibleState) {
+        self.inner.update_state(visible_state);
+    }
+
+    /// Passed straight through, so that controllers with their own `decide` are recorded as they
+    /// are, including any answers the engine rejects.
+    fn decide(&mut self, pending: &PendingDecision) -> Decision {
+        let decision = self.inner.decide(pending);
+        self.record(decision.clone());
+        decision
+    }
+
+    fn play_action(&mut self) -> Option<Action> {
+        let action = self.inner.play_action();
+        self.record(Decision::PlayAction(action.clone()));
+        action
+    }
+
+    fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        let breathe_or_travel = self.inner.breathe_or_travel();
+        self.record(Decision::BreatheOrTravel(breathe_or_travel));
+        breathe_or_travel
+    }
+
+    fn defend(&mut self, action: &Action) -> bool {
+        let defend = self.inner.defend(action);
+        self.record(Decision::Defend(defend));
+        defend
+    }
+
+    fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+        let cards = self.inner.forced_discard(card_count);
+        self.record(Decision::ForcedDiscard(cards.clone()));
+        cards
+    }
+
+    fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+        let player_reference = self.inner.choose_player_to_swap_with();
+        self.record(Decision::SwapWith(player_reference));
+        player_reference
+    }
+
+    fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+        let card = self.inner.choose_card_to_take(options);
+        self.record(Decision::TakeCard(card));
+        card
+    }
+}
+
+impl Replay {
+    pub fn save(&self, path: impl AsRef<Path>) -> miette::Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Creating {}", path.display()))?;
+        self.to_writer(BufWriter::new(file))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> miette::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Opening {}", path.display()))?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    pub fn to_writer(&self, writer: impl Write) -> miette::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+            .into_diagnostic()
+            .wrap_err("Writing replay.")
+    }
+
+    pub fn from_reader(reader: impl Read) -> miette::Result<Self> {
+        let replay: Replay = serde_json::from_reader(reader)
+            .into_diagnostic()
+            .wrap_err("Reading replay.")?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion {
+                found: replay.version,
+                expected: REPLAY_VERSION,
+            }
+            .into());
+        }
+        Ok(replay)
+    }
+
+    /// Play the game again from the recorded decisions, returning the finished game.
+    ///
+    /// Fails if the engine asks for a different decision than was recorded, or if the events
+    /// don't serialize to exactly the same bytes as the recording.
+    pub fn verify(&self) -> miette::Result<Game> {
+        let desync = Rc::new(RefCell::new(None));
+        let queues: Vec<Rc<RefCell<VecDeque<Decision>>>> = (0..self.player_count)
+            .map(|idx| {
+                let decisions = self
+                    .decisions
+                    .iter()
+                    .filter(|recorded| recorded.player == PlayerReference(idx))
+                    .map(|recorded| recorded.decision.clone())
+                    .collect();
+                Rc::new(RefCell::new(decisions))
+            })
+            .collect();
+        let controllers = queues
+            .iter()
+            .enumerate()
+            .map(|(idx, decisions)| {
+                Box::new(ReplayController {
+                    player: PlayerReference(idx),
+                    decisions: decisions.clone(),
+                    desync: desync.clone(),
+                }) as Box<dyn PlayerController>
+            })
+            .collect();
+
+        let state = GameState::with_rules(Some(self.seed), self.player_count, self.rules.clone())?;
+        let mut game = Game::from_state(state, controllers);
+        game.set_misconduct_policy(self.misconduct_policy);
+        let recorder = Recorder::new();
+        recorder.attach(&mut game);
+        let result = game.simulate();
+
+        // A desync explains any engine error that follows it, so report it first.
+        if let Some(err) = desync.borrow_mut().take() {
+            return Err(err.into());
+        }
+        result?;
+
+        let remaining: usize = queues
+            .iter()
+            .map(|decisions| decisions.borrow().len())
+            .sum();
+        if remaining > 0 {
+            return Err(ReplayError::UnusedDecisions { remaining }.into());
+        }
+
+        let replayed = recorder.events.borrow();
+        let count = self.events.len().max(replayed.len());
+        for index in 0..count {
+            let recorded = event_json(self.events.get(index))?;
+            let replayed = event_json(replayed.get(index))?;
+            if recorded.as_bytes() != replayed.as_bytes() {
+                return Err(ReplayError::EventMismatch {
+                    index,
+                    recorded,
+                    replayed,
+                }
+                .into());
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+fn event_json(event: Option<&GameEvent>) -> miette::Result<String> {
+    serde_json::to_string(&event).into_diagnostic()
+}
+
+/// Answers the engine with a seat's recorded decisions, in order.
+struct ReplayController {
+    player: PlayerReference,
+    decisions: Rc<RefCell<VecDeque<Decision>>>,
+    desync: Rc<RefCell<Option<ReplayError>>>,
+}
+
+impl PlayerController for ReplayController {
+    fn update_state(&mut self, _visible_state: VisibleState) {}
+
+    /// Whatever was recorded next, even if it isn't what was asked for. A decision the engine
+    /// rejected when the game was played is rejected again, and handled by the same
+    /// `MisconductPolicy`.
+    fn decide(&mut self, pending: &PendingDecision) -> Decision {
+        if let Some(decision) = self.decisions.borrow_mut().pop_front() {
+            return decision;
+        }
+        let mut desync = self.desync.borrow_mut();
+        if desync.is_none() {
+            *desync = Some(ReplayError::Desync {
+                player: self.player,
+                asked: pending.kind(),
+                recorded: "nothing left".to_string(),
+            });
+        }
+        Decision::PlayAction(None)
+    }
+
+    // Never asked, since `decide` answers everything from the recording.
+
+    fn play_action(&mut self) -> Option<Action> {
+        None
+    }
+
+    fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        BreatheOrTravel::Breathe
+    }
+
+    fn defend(&mut self, _action: &Action) -> bool {
+        false
+    }
+
+    fn forced_discard(&mut self, _card_count: usize) -> Vec<GameCard> {
+        Vec::new()
+    }
+
+    fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+        self.player
+    }
+
+    fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+        options.into_iter().min().unwrap_or(GameCard::O1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzz::AdversarialController;
+    use crate::{Penalty, RandomPlayerController};
+
+    fn record(seed: u64, players: usize) -> Replay {
+        let recorder = Recorder::new();
+        let controllers = (0..players as u64)
+            .map(|seat| {
+                Box::new(RandomPlayerController::with_seed(seed * 10 + seat))
+                    as Box<dyn PlayerController>
+            })
+            .collect();
+        let mut game = Game::new(Some(seed), recorder.wrap_all(controllers));
+        recorder.attach(&mut game);
+        game.simulate().unwrap();
+        recorder.finish(&game)
+    }
+
+    #[test]
+    fn test_replay_round_trip() {
+        for seed in 0..20 {
+            let replay = record(seed, 4);
+            let mut bytes = Vec::new();
+            replay.to_writer(&mut bytes).unwrap();
+            let loaded = Replay::from_reader(bytes.as_slice()).unwrap();
+            assert_eq!(loaded, replay);
+
+            let game = loaded.verify().unwrap();
+            assert!(game.winner().is_some());
+        }
+    }
+
+    #[test]
+    fn test_replay_keeps_rules() {
+        let rules = RuleSet {
+            meteoroid_hand_limit: 4,
+            oxygen_siphon_count: 1,
+            ..RuleSet::default()
+        };
+        let recorder = Recorder::new();
+        let controllers = (0..3)
+            .map(|seat| {
+                Box::new(RandomPlayerController::with_seed(seat)) as Box<dyn PlayerController>
+            })
+            .collect();
+        let mut game =
+            Game::with_rules(Some(3), rules.clone(), recorder.wrap_all(controllers)).unwrap();
+        recorder.attach(&mut game);
+        game.simulate().unwrap();
+        let replay = recorder.finish(&game);
+
+        assert_eq!(replay.rules.ship_distance, Some(6));
+        assert_eq!(
+            replay.rules,
+            RuleSet {
+                ship_distance: Some(6),
+                ..rules
+            }
+        );
+        assert_eq!(replay.verify().unwrap().rules(), &replay.rules);
+    }
+
+    #[test]
+    fn test_replay_keeps_misconduct() {
+        let policy = MisconductPolicy {
+            retries: 1,
+            penalty: Penalty::Penalize,
+        };
+        for seed in 0..10 {
+            let recorder = Recorder::new();
+            let controllers = (0..3)
+                .map(|seat| {
+                    Box::new(AdversarialController::new(seed * 10 + seat, 0.3))
+                        as Box<dyn PlayerController>
+                })
+                .collect();
+            let mut game = Game::new(Some(seed), recorder.wrap_all(controllers));
+            game.set_misconduct_policy(policy);
+            recorder.attach(&mut game);
+            game.simulate().unwrap();
+            let replay = recorder.finish(&game);
+
+            assert_eq!(replay.misconduct_policy, policy);
+            assert!(replay
+                .events
+                .iter()
+                .any(|event| matches!(event, GameEvent::DecisionRejected { .. })));
+            replay.verify().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_replay_detects_tampering() {
+        let mut replay = record(7, 3);
+        let first_pass = replay
+            .decisions
+            .iter()
+            .position(|recorded| matches!(recorded.decision, Decision::PlayAction(None)))
+            .unwrap();
+        replay.decisions.remove(first_pass);
+        assert!(replay.verify().is_err());
+    }
+
+    #[test]
+    fn test_replay_rejects_other_versions() {
+        let mut replay = record(1, 2);
+        replay.version = REPLAY_VERSION + 1;
+        let mut bytes = Vec::new();
+        replay.to_writer(&mut bytes).unwrap();
+        assert!(Replay::from_reader(bytes.as_slice()).is_err());
+    }
+}
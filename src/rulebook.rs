@@ -0,0 +1,799 @@
+//! The official rulebook, one test per clause, each played out from a `Scenario`.
+
+use crate::{
+    Action, BreatheOrTravel, DeathCause, Decision, GameCard, GameEvent, GameState, PendingDecision,
+    Phase, PlayerReference, RuleSet, Scenario, SpaceCard, WinReason,
+};
+use GameCard::{HoleInSuit, LaserBlast, Shield, Tether, O1, O2};
+
+/// Set up the position and play until the first decision, checking invariants all the way.
+fn play_from(text: &str) -> GameState {
+    let mut state = Scenario::parse("rulebook", text).unwrap().build().unwrap();
+    state.set_check_invariants(true);
+    state.start().unwrap();
+    state
+}
+
+fn play(state: &mut GameState, action: Action) -> miette::Result<Option<PendingDecision>> {
+    state.apply(Decision::PlayAction(Some(action)))
+}
+
+fn hand(state: &GameState, player: usize) -> Vec<GameCard> {
+    let mut hand = state.player(&PlayerReference(player)).unwrap().hand.clone();
+    hand.sort();
+    hand
+}
+
+fn space(state: &GameState, player: usize) -> Vec<SpaceCard> {
+    state
+        .player(&PlayerReference(player))
+        .unwrap()
+        .space
+        .clone()
+}
+
+fn alive(state: &GameState, player: usize) -> bool {
+    state.player(&PlayerReference(player)).unwrap().alive
+}
+
+fn died_of(state: &mut GameState, player: usize) -> Option<DeathCause> {
+    state
+        .drain_events()
+        .into_iter()
+        .find_map(|event| match event {
+            GameEvent::PlayerDied {
+                player: died,
+                cause,
+            } if died.0 == player => Some(cause),
+            _ => None,
+        })
+}
+
+// Setup: everybody starts with four O1s and an O2.
+
+#[test]
+fn test_setup_deals_four_o1s_and_an_o2() {
+    let state = GameState::new(Some(3), 4);
+    for player in 0..4 {
+        assert_eq!(hand(&state, player), vec![O1, O1, O1, O1, O2]);
+        assert!(space(&state, player).is_empty());
+    }
+}
+
+// A turn starts by picking up a card.
+
+#[test]
+fn test_turn_starts_by_picking_up_a_card() {
+    let state = play_from(
+        "players 2
+         player 0: hand O1 O2
+         deck Tether ...",
+    );
+    assert_eq!(hand(&state, 0), vec![O1, O2, Tether]);
+    assert_eq!(
+        state.pending_decision(),
+        Some(&PendingDecision::PlayAction {
+            player: PlayerReference(0),
+        })
+    );
+}
+
+// Then any number of action cards can be played, before breathing or travelling.
+
+#[test]
+fn test_actions_can_follow_each_other() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand RocketBooster RocketBooster O1
+         space deck BlankSpace BlankSpace ...",
+    );
+    play(&mut state, Action::RocketBooster).unwrap();
+    let pending = play(&mut state, Action::RocketBooster).unwrap();
+    assert_eq!(space(&state, 0), vec![SpaceCard::BlankSpace; 2]);
+    assert_eq!(
+        pending,
+        Some(PendingDecision::PlayAction {
+            player: PlayerReference(0),
+        })
+    );
+}
+
+// O1: breathing uses an O1, and the player stays where they are.
+
+#[test]
+fn test_breathing_costs_an_o1() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O1 O2",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Breathe))
+        .unwrap();
+    assert_eq!(hand(&state, 0), vec![O2]);
+    assert!(space(&state, 0).is_empty());
+    assert_eq!(state.whose_turn(), PlayerReference(1));
+}
+
+// O2: travelling uses an O2 and reveals the next space card.
+
+#[test]
+fn test_travelling_costs_an_o2() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O1 O2
+         space deck BlankSpace ...",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert_eq!(hand(&state, 0), vec![O1]);
+    assert_eq!(space(&state, 0), vec![SpaceCard::BlankSpace]);
+}
+
+// A player who can neither breathe nor travel runs out of oxygen.
+
+#[test]
+fn test_no_oxygen_is_fatal() {
+    let mut state = play_from(
+        "players 3
+         phase breathe
+         player 0: hand Tether",
+    );
+    assert!(!alive(&state, 0));
+    assert_eq!(died_of(&mut state, 0), Some(DeathCause::NoOxygen));
+    assert_eq!(state.whose_turn(), PlayerReference(1));
+}
+
+// Dead players are out: their hand is discarded and their turns are skipped.
+
+#[test]
+fn test_dead_players_discard_their_hand() {
+    let mut state = play_from(
+        "players 3
+         phase actions
+         player 0: hand HoleInSuit O1 O2
+         player 1: hand Tether Shield; space BlankSpace
+         player 2: hand O1",
+    );
+    let pending = play(
+        &mut state,
+        Action::HoleInSuit {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        pending,
+        Some(PendingDecision::Defend {
+            player: PlayerReference(1),
+            action: Action::HoleInSuit {
+                target: PlayerReference(1),
+            },
+        })
+    );
+    state.apply(Decision::Defend(false)).unwrap();
+    assert!(!alive(&state, 1));
+    assert!(hand(&state, 1).is_empty());
+    assert_eq!(space(&state, 1), vec![SpaceCard::BlankSpace]);
+    assert!(state.discard_pile().ends_with(&[Tether, Shield]));
+
+    state.apply(Decision::PlayAction(None)).unwrap();
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Breathe))
+        .unwrap();
+    assert_eq!(state.whose_turn(), PlayerReference(2));
+}
+
+// The first player to reach the ship wins.
+
+#[test]
+fn test_reaching_the_ship_wins() {
+    let mut state = play_from(
+        "players 2
+         ship 2
+         phase breathe
+         player 0: hand O2; space BlankSpace
+         space deck CosmicRadiation ...",
+    );
+    assert_eq!(state.winner(), Some(PlayerReference(0)));
+    // The hazard at the ship is never resolved.
+    assert!(alive(&state, 0));
+    assert!(state.drain_events().contains(&GameEvent::GameWon {
+        player: PlayerReference(0),
+        reason: WinReason::ReachedShip,
+    }));
+}
+
+// So does the last player left alive.
+
+#[test]
+fn test_last_survivor_wins() {
+    let mut state = play_from(
+        "players 3
+         phase actions
+         player 0: hand HoleInSuit O2
+         player 1: hand Tether
+         player 2: dead",
+    );
+    play(
+        &mut state,
+        Action::HoleInSuit {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(state.winner(), Some(PlayerReference(0)));
+    assert!(state.drain_events().contains(&GameEvent::GameWon {
+        player: PlayerReference(0),
+        reason: WinReason::LastSurvivor,
+    }));
+}
+
+// Oxygen siphon: take two oxygen from another player.
+
+#[test]
+fn test_oxygen_siphon_takes_two_oxygen() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand OxygenSiphon O2
+         player 1: hand O1 O1 O1 Tether",
+    );
+    play(
+        &mut state,
+        Action::OxygenSiphon {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(hand(&state, 0), vec![O1, O1, O2]);
+    assert_eq!(hand(&state, 1), vec![O1, Tether]);
+}
+
+// A player without the oxygen to pay a siphon dies, and the siphon takes what they had.
+
+#[test]
+fn test_oxygen_siphon_without_enough_oxygen_kills() {
+    let mut state = play_from(
+        "players 3
+         phase actions
+         player 0: hand OxygenSiphon O2
+         player 1: hand O1 Tether",
+    );
+    play(
+        &mut state,
+        Action::OxygenSiphon {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert!(!alive(&state, 1));
+    assert_eq!(died_of(&mut state, 1), Some(DeathCause::OxygenSiphon));
+    assert_eq!(hand(&state, 0), vec![O1, O2]);
+}
+
+// A siphon is paid in oxygen, not cards, so a single O2 covers it.
+
+#[test]
+fn test_oxygen_siphon_takes_an_o2() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand OxygenSiphon O2
+         player 1: hand O2 Tether",
+    );
+    let siphon = Action::OxygenSiphon {
+        target: PlayerReference(1),
+    };
+    assert!(state.legal_actions(&PlayerReference(0)).contains(&siphon));
+    play(&mut state, siphon).unwrap();
+    assert_eq!(hand(&state, 0), vec![O2, O2]);
+    assert_eq!(hand(&state, 1), vec![Tether]);
+}
+
+// An O2 worth more than a siphon still needs is broken into O1s, so the siphoning player gets
+// exactly the siphon and the target keeps the rest.
+
+#[test]
+fn test_odd_oxygen_siphon_makes_change() {
+    let rules = RuleSet {
+        oxygen_siphon_count: 3,
+        ..RuleSet::default()
+    };
+    let mut state = Scenario::with_rules(2, rules)
+        .phase(Phase::Actions)
+        .hand(0, [GameCard::OxygenSiphon, O2])
+        .hand(1, [O2, O2, Tether])
+        .discard_pile([O1, O1])
+        .build()
+        .unwrap();
+    state.set_check_invariants(true);
+    state.start().unwrap();
+    play(
+        &mut state,
+        Action::OxygenSiphon {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(hand(&state, 0), vec![O1, O2, O2]);
+    assert_eq!(hand(&state, 1), vec![O1, Tether]);
+    assert_eq!(state.discard_pile(), &[GameCard::OxygenSiphon, O2]);
+}
+
+// Shield: block an attack played on you.
+
+#[test]
+fn test_shield_blocks_an_attack() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand LaserBlast O2
+         player 1: hand Shield O1; space BlankSpace",
+    );
+    let action = Action::LaserBlast {
+        target: PlayerReference(1),
+    };
+    let pending = play(&mut state, action.clone()).unwrap();
+    assert_eq!(
+        pending,
+        Some(PendingDecision::Defend {
+            player: PlayerReference(1),
+            action,
+        })
+    );
+    state.apply(Decision::Defend(true)).unwrap();
+    assert_eq!(space(&state, 1), vec![SpaceCard::BlankSpace]);
+    assert_eq!(hand(&state, 1), vec![O1]);
+    assert_eq!(state.discard_pile().last(), Some(&Shield));
+}
+
+// Hack suit: look at another player's hand and take the card you want.
+
+#[test]
+fn test_hack_suit_takes_a_chosen_card() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand HackSuit O2
+         player 1: hand Tether O1 O1",
+    );
+    let pending = play(
+        &mut state,
+        Action::HackSuit {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    let Some(PendingDecision::ChooseCardToTake { options, .. }) = pending else {
+        panic!("expected to choose a card, got {:?}", pending);
+    };
+    assert_eq!(options.len(), 2);
+    assert!(options.contains(&O1) && options.contains(&Tether));
+    state.apply(Decision::TakeCard(Tether)).unwrap();
+    assert_eq!(hand(&state, 0), vec![O2, Tether]);
+    assert_eq!(hand(&state, 1), vec![O1, O1]);
+}
+
+// Tractor beam: take a random card from another player.
+
+#[test]
+fn test_tractor_beam_takes_a_random_card() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand TractorBeam O2
+         player 1: hand Tether",
+    );
+    play(
+        &mut state,
+        Action::TractorBeam {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(hand(&state, 0), vec![O2, Tether]);
+    assert!(hand(&state, 1).is_empty());
+}
+
+// Rocket booster: travel a space without using any oxygen.
+
+#[test]
+fn test_rocket_booster_travels_for_free() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand RocketBooster O1 O2
+         space deck UsefulJunk ...
+         deck Tether ...",
+    );
+    play(&mut state, Action::RocketBooster).unwrap();
+    assert_eq!(space(&state, 0), vec![SpaceCard::UsefulJunk]);
+    // The space card it lands on takes effect.
+    assert_eq!(hand(&state, 0), vec![O1, O2, Tether]);
+}
+
+// Laser blast: knock another player back a space, without their space card taking effect again.
+
+#[test]
+fn test_laser_blast_knocks_a_player_back() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand LaserBlast O2
+         player 1: hand O1 O1; space BlankSpace CosmicRadiation",
+    );
+    play(
+        &mut state,
+        Action::LaserBlast {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(space(&state, 1), vec![SpaceCard::BlankSpace]);
+    assert_eq!(hand(&state, 1), vec![O1, O1]);
+    assert_eq!(state.space_deck().1, &[SpaceCard::CosmicRadiation]);
+}
+
+// Only players who have travelled can be knocked back.
+
+#[test]
+fn test_laser_blast_needs_a_target_in_space() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand LaserBlast O2",
+    );
+    let action = Action::LaserBlast {
+        target: PlayerReference(1),
+    };
+    assert!(play(&mut state, action.clone()).is_err());
+    assert!(!state.legal_actions(&PlayerReference(0)).contains(&action));
+}
+
+// Hole in suit: another player loses an oxygen.
+
+#[test]
+fn test_hole_in_suit_costs_an_oxygen() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand HoleInSuit O2
+         player 1: hand O1 O1 Tether",
+    );
+    play(
+        &mut state,
+        Action::HoleInSuit {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(hand(&state, 1), vec![O1, Tether]);
+    assert_eq!(state.discard_pile(), &[HoleInSuit, O1]);
+}
+
+// An O2 can pay for a single oxygen, with an O1 back in change.
+
+#[test]
+fn test_o2_pays_for_one_oxygen_with_change() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand HoleInSuit O2
+         player 1: hand O2 Tether
+         discard O1",
+    );
+    play(
+        &mut state,
+        Action::HoleInSuit {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(hand(&state, 1), vec![O1, Tether]);
+    assert_eq!(state.discard_pile(), &[HoleInSuit, O2]);
+}
+
+// Tether: pull another player's last space card onto the end of your own row, without it taking
+// effect.
+
+#[test]
+fn test_tether_pulls_a_space_card_over() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand Tether O1; space BlankSpace
+         player 1: space BlankSpace CosmicRadiation",
+    );
+    play(
+        &mut state,
+        Action::Tether {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        space(&state, 0),
+        vec![SpaceCard::BlankSpace, SpaceCard::CosmicRadiation]
+    );
+    assert_eq!(space(&state, 1), vec![SpaceCard::BlankSpace]);
+    assert_eq!(hand(&state, 0), vec![O1]);
+}
+
+// Blank space: nothing happens.
+
+#[test]
+fn test_blank_space() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 O1 Tether
+         space deck BlankSpace ...",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert_eq!(space(&state, 0), vec![SpaceCard::BlankSpace]);
+    assert_eq!(hand(&state, 0), vec![O1, Tether]);
+}
+
+// Useful junk: pick up a card.
+
+#[test]
+fn test_useful_junk_draws_a_card() {
+    let state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2
+         deck Tether ...
+         space deck UsefulJunk ...",
+    );
+    assert_eq!(hand(&state, 0), vec![Tether]);
+}
+
+// Mysterious nebula: pick up two cards.
+
+#[test]
+fn test_mysterious_nebula_draws_two_cards() {
+    let state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2
+         deck Tether Shield ...
+         space deck MysteriousNebula ...",
+    );
+    assert_eq!(hand(&state, 0), vec![Shield, Tether]);
+}
+
+// Hyperspace: travel on to the next space card for free.
+
+#[test]
+fn test_hyperspace_travels_again() {
+    let state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2
+         deck Tether ...
+         space deck Hyperspace UsefulJunk ...",
+    );
+    assert_eq!(
+        space(&state, 0),
+        vec![SpaceCard::Hyperspace, SpaceCard::UsefulJunk]
+    );
+    assert_eq!(hand(&state, 0), vec![Tether]);
+}
+
+// Meteoroid: a player holding more than six cards discards two of their choice.
+
+#[test]
+fn test_meteoroid_hits_big_hands() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 O1*4 Tether LaserBlast Shield
+         space deck Meteoroid ...",
+    );
+    let pending = state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert_eq!(
+        pending,
+        Some(PendingDecision::ForcedDiscard {
+            player: PlayerReference(0),
+            card_count: 2,
+        })
+    );
+    state
+        .apply(Decision::ForcedDiscard(vec![Tether, LaserBlast]))
+        .unwrap();
+    assert_eq!(hand(&state, 0), vec![O1, O1, O1, O1, Shield]);
+}
+
+#[test]
+fn test_meteoroid_misses_small_hands() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 O1*4 Tether LaserBlast
+         space deck Meteoroid ...",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert_eq!(hand(&state, 0).len(), 6);
+    assert_eq!(state.whose_turn(), PlayerReference(1));
+}
+
+// Cosmic radiation: lose an oxygen.
+
+#[test]
+fn test_cosmic_radiation_costs_an_oxygen() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 O1 O1
+         space deck CosmicRadiation ...",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert_eq!(hand(&state, 0), vec![O1]);
+    assert!(alive(&state, 0));
+}
+
+#[test]
+fn test_cosmic_radiation_without_oxygen_kills() {
+    let mut state = play_from(
+        "players 3
+         phase breathe
+         player 0: hand O2 Tether
+         space deck CosmicRadiation ...",
+    );
+    assert!(!alive(&state, 0));
+    assert_eq!(died_of(&mut state, 0), Some(DeathCause::CosmicRadiation));
+}
+
+// Asteroid field: lose two oxygen.
+
+#[test]
+fn test_asteroid_field_costs_two_oxygen() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 O1 O1 O1
+         space deck AsteroidField ...",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert_eq!(hand(&state, 0), vec![O1]);
+    assert!(alive(&state, 0));
+}
+
+#[test]
+fn test_asteroid_field_without_oxygen_kills() {
+    let mut state = play_from(
+        "players 3
+         phase breathe
+         player 0: hand O2 O1
+         space deck AsteroidField ...",
+    );
+    state
+        .apply(Decision::BreatheOrTravel(BreatheOrTravel::Travel))
+        .unwrap();
+    assert!(!alive(&state, 0));
+    let death = state
+        .drain_events()
+        .into_iter()
+        .find(|event| matches!(event, GameEvent::PlayerDied { .. }))
+        .unwrap();
+    assert_eq!(
+        death,
+        GameEvent::PlayerDied {
+            player: PlayerReference(0),
+            cause: DeathCause::AsteroidField,
+        }
+    );
+    assert!(death.to_string().contains("asteroid field"));
+}
+
+// Gravitational anomaly: get pulled back a space, losing the anomaly and the card before it.
+
+#[test]
+fn test_gravitational_anomaly_pulls_back() {
+    let mut state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 Tether; space BlankSpace UsefulJunk
+         space deck GravitationalAnomaly ...",
+    );
+    assert_eq!(space(&state, 0), vec![SpaceCard::BlankSpace]);
+    assert_eq!(
+        state.space_deck().1,
+        &[SpaceCard::GravitationalAnomaly, SpaceCard::UsefulJunk]
+    );
+    assert!(state.drain_events().contains(&GameEvent::SpaceCardLost {
+        player: PlayerReference(0),
+        card: SpaceCard::UsefulJunk,
+    }));
+}
+
+#[test]
+fn test_gravitational_anomaly_at_the_start() {
+    let state = play_from(
+        "players 2
+         phase breathe
+         player 0: hand O2 Tether
+         space deck GravitationalAnomaly ...",
+    );
+    assert!(space(&state, 0).is_empty());
+    assert_eq!(state.space_deck().1, &[SpaceCard::GravitationalAnomaly]);
+}
+
+// Worm hole: swap space rows with any other player.
+
+#[test]
+fn test_worm_hole_swaps_rows() {
+    let mut state = play_from(
+        "players 3
+         phase breathe
+         player 0: hand O2 Tether; space BlankSpace
+         player 1: space BlankSpace*3
+         player 2: dead
+         space deck WormHole ...",
+    );
+    assert_eq!(
+        state.pending_decision(),
+        Some(&PendingDecision::ChooseSwapTarget {
+            player: PlayerReference(0),
+        })
+    );
+    assert!(state.apply(Decision::SwapWith(PlayerReference(0))).is_err());
+    assert!(state.apply(Decision::SwapWith(PlayerReference(2))).is_err());
+    state.apply(Decision::SwapWith(PlayerReference(1))).unwrap();
+    assert_eq!(space(&state, 0), vec![SpaceCard::BlankSpace; 3]);
+    assert_eq!(
+        space(&state, 1),
+        vec![SpaceCard::BlankSpace, SpaceCard::WormHole]
+    );
+}
+
+// Solar flare: a player in a solar flare can't play action cards...
+
+#[test]
+fn test_solar_flare_stops_actions() {
+    let state = play_from(
+        "players 2
+         phase actions
+         player 0: hand RocketBooster O1 O2; space SolarFlare",
+    );
+    assert_eq!(
+        state.pending_decision(),
+        Some(&PendingDecision::BreatheOrTravel {
+            player: PlayerReference(0),
+        })
+    );
+}
+
+// ...or shields.
+
+#[test]
+fn test_solar_flare_stops_shields() {
+    let mut state = play_from(
+        "players 2
+         phase actions
+         player 0: hand HoleInSuit O2
+         player 1: hand Shield O1 O1; space SolarFlare",
+    );
+    play(
+        &mut state,
+        Action::HoleInSuit {
+            target: PlayerReference(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(hand(&state, 1), vec![O1, Shield]);
+}
@@ -0,0 +1,251 @@
+use crate::errors::{parse_toml, TomlError};
+use crate::{DeckConfig, DeckError, GameCard, GameState, OxygenChange};
+use miette::{Diagnostic, IntoDiagnostic, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum RuleError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] TomlError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Decks(#[from] DeckError),
+
+    #[error("A game needs at least two players, not {0}.")]
+    NotEnoughPlayers(usize),
+
+    #[error("The ship can't be 0 spaces away.")]
+    ShipTooClose,
+
+    #[error("A meteoroid can't make a player discard {discard} cards when they only need more than {hand_limit} to be hit.")]
+    MeteoroidDiscard { discard: usize, hand_limit: usize },
+
+    #[error("With {player_count} players the space deck needs at least {needed} cards, so that it can't run out before somebody reaches the ship, but it only has {available}.")]
+    #[diagnostic(help("Add more space cards or bring the ship closer."))]
+    NotEnoughSpace {
+        player_count: usize,
+        needed: usize,
+        available: usize,
+    },
+}
+
+/// Everything about the rules that can be changed, for variants and balance studies.
+///
+/// The default is the official base game. In TOML every field is optional and falls back to the
+/// official rule, so a variant only needs to list what it changes:
+///
+/// ```toml
+/// meteoroid_discard = 3
+///
+/// [house_rules]
+/// actions_in_solar_flare = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleSet {
+    /// Space cards needed to reach the ship. By default it depends on the number of players, see
+    /// `GameState::default_ship_distance`.
+    pub ship_distance: Option<usize>,
+
+    /// O2 cards each player starts with.
+    pub starting_o2: usize,
+
+    /// O1 cards each player starts with.
+    pub starting_o1: usize,
+
+    /// A meteoroid hits players holding more than this many cards.
+    pub meteoroid_hand_limit: usize,
+
+    /// Cards a player hit by a meteoroid must discard.
+    pub meteoroid_discard: usize,
+
+    /// Oxygen lost flying through an asteroid field.
+    pub asteroid_field_damage: usize,
+
+    /// Oxygen an oxygen siphon takes from its target.
+    pub oxygen_siphon_count: usize,
+
+    /// Where the change comes from when somebody pays for oxygen with more than they owe.
+    pub oxygen_change: OxygenChange,
+
+    pub house_rules: HouseRules,
+
+    pub decks: DeckConfig,
+}
+
+/// Popular changes to the official rules, all off by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HouseRules {
+    /// Players in a solar flare can still play action cards.
+    pub actions_in_solar_flare: bool,
+
+    /// Players in a solar flare can still use shields.
+    pub shields_in_solar_flare: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            ship_distance: None,
+            starting_o2: 1,
+            starting_o1: 4,
+            meteoroid_hand_limit: 6,
+            meteoroid_discard: 2,
+            asteroid_field_damage: 2,
+            oxygen_siphon_count: 2,
+            oxygen_change: OxygenChange::default(),
+            house_rules: HouseRules::default(),
+            decks: DeckConfig::base(),
+        }
+    }
+}
+
+impl RuleSet {
+    /// Parse and validate rules, naming the source in any error.
+    pub fn from_toml(name: &str, text: &str) -> Result<Self, RuleError> {
+        let rules: RuleSet = parse_toml(name, text)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> miette::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Reading {}", path.display()))?;
+        Ok(Self::from_toml(&path.display().to_string(), &text)?)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("rules are always representable in TOML")
+    }
+
+    /// How many space cards a player needs in front of them to reach the ship.
+    pub fn ship_distance(&self, player_count: usize) -> usize {
+        self.ship_distance
+            .unwrap_or_else(|| GameState::default_ship_distance(player_count))
+    }
+
+    /// The cards dealt to each player, in the order they are dealt.
+    pub fn starting_hand(&self) -> [(GameCard, usize); 2] {
+        [
+            (GameCard::O2, self.starting_o2),
+            (GameCard::O1, self.starting_o1),
+        ]
+    }
+
+    /// Checks that don't depend on the number of players.
+    pub fn validate(&self) -> Result<(), RuleError> {
+        if self.ship_distance == Some(0) {
+            return Err(RuleError::ShipTooClose);
+        }
+        if self.meteoroid_discard > self.meteoroid_hand_limit + 1 {
+            return Err(RuleError::MeteoroidDiscard {
+                discard: self.meteoroid_discard,
+                hand_limit: self.meteoroid_hand_limit,
+            });
+        }
+        self.decks.validate()?;
+        Ok(())
+    }
+
+    /// Whether a game can be played with this many players.
+    pub fn check(&self, player_count: usize) -> Result<(), RuleError> {
+        if player_count < 2 {
+            return Err(RuleError::NotEnoughPlayers(player_count));
+        }
+        self.validate()?;
+        self.decks.check_deal(player_count, &self.starting_hand())?;
+
+        // Every player can be one card short of the ship with one more left to draw.
+        let needed = player_count * (self.ship_distance(player_count) - 1) + 1;
+        let available = self.decks.space_deck().len();
+        if available < needed {
+            return Err(RuleError::NotEnoughSpace {
+                player_count,
+                needed,
+                available,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_rules() {
+        let text = "meteoroid_discard = 3\n\n[house_rules]\nactions_in_solar_flare = true\n";
+        let rules = RuleSet::from_toml("variant.toml", text).unwrap();
+        assert_eq!(rules.meteoroid_discard, 3);
+        assert!(rules.house_rules.actions_in_solar_flare);
+        assert!(!rules.house_rules.shields_in_solar_flare);
+        assert_eq!(rules.starting_o1, 4);
+        assert_eq!(rules.decks, DeckConfig::base());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rules = RuleSet {
+            ship_distance: Some(3),
+            oxygen_siphon_count: 1,
+            oxygen_change: OxygenChange::Deck,
+            ..RuleSet::default()
+        };
+        assert_eq!(RuleSet::from_toml("copy", &rules.to_toml()).unwrap(), rules);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(matches!(
+            RuleSet::from_toml("variant.toml", "ship_distance = 0"),
+            Err(RuleError::ShipTooClose)
+        ));
+        assert!(matches!(
+            RuleSet::from_toml("variant.toml", "meteoroid_discard = 8"),
+            Err(RuleError::MeteoroidDiscard { .. })
+        ));
+        assert!(matches!(
+            RuleSet::from_toml("variant.toml", "starting_o2 = 1\nstarting_o3 = 1"),
+            Err(RuleError::Parse(_))
+        ));
+
+        for player_count in [0, 1] {
+            assert!(matches!(
+                RuleSet::default().check(player_count),
+                Err(RuleError::NotEnoughPlayers(count)) if count == player_count
+            ));
+        }
+
+        let rules = RuleSet {
+            starting_o1: 10,
+            ..RuleSet::default()
+        };
+        assert!(rules.check(3).is_ok());
+        assert!(matches!(
+            rules.check(4),
+            Err(RuleError::Decks(DeckError::NotEnoughToDeal { .. }))
+        ));
+
+        let rules = RuleSet {
+            ship_distance: Some(9),
+            ..RuleSet::default()
+        };
+        assert!(rules.check(5).is_ok());
+        assert!(matches!(
+            rules.check(6),
+            Err(RuleError::NotEnoughSpace {
+                needed: 49,
+                available: 42,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,815 @@
+use crate::{
+    GameCard, GameDeck, GameState, Phase, Player, PlayerReference, RuleError, RuleSet, SpaceCard,
+    SpaceDeck,
+};
+use miette::{Diagnostic, IntoDiagnostic, NamedSource, SourceSpan, WrapErr};
+use rand::prelude::SliceRandom;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum ScenarioError {
+    #[error("Couldn't read {name} line {line}: {message}")]
+    Parse {
+        name: String,
+        line: usize,
+        message: String,
+        #[source_code]
+        source_code: NamedSource,
+        #[label("here")]
+        span: SourceSpan,
+    },
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Rules(#[from] RuleError),
+
+    #[error("There is no player {0}.")]
+    NoSuchPlayer(usize),
+
+    #[error("Player {0} is dead but holds cards.")]
+    DeadPlayerHoldsCards(usize),
+
+    #[error("At least two players must be alive.")]
+    NotEnoughPlayersAlive,
+
+    #[error("It can't be dead player {0}'s turn once it has started.")]
+    #[diagnostic(help("Use the pickup phase to move on to the next living player."))]
+    TurnOfDeadPlayer(usize),
+
+    #[error("Player {0} has already reached the ship.")]
+    AlreadyAtShip(usize),
+
+    #[error("The scenario uses {placed} {card} cards but the decks only have {available}.")]
+    TooManyCards {
+        card: String,
+        placed: usize,
+        available: usize,
+    },
+
+    #[error("The {pile} is given exactly, but {count} {card} cards from the decks are left over.")]
+    #[diagnostic(help("End the pile with ... to shuffle the rest of the cards in below it."))]
+    LeftOverCards {
+        pile: &'static str,
+        card: String,
+        count: usize,
+    },
+}
+
+/// A position to start a game from, for rules tests, puzzles and benchmarks.
+///
+/// Every player's hand, space row and whether they are alive can be set, along with whose turn
+/// and phase it is and the order of the decks. Cards the scenario doesn't place are shuffled
+/// into the decks, under any cards given for the top, so that every card in the rules' decks is
+/// somewhere exactly once.
+///
+/// Build one in code:
+///
+/// ```
+/// use selfish::{GameCard, Phase, Scenario, SpaceCard};
+///
+/// let state = Scenario::new(2)
+///     .hand(0, [GameCard::O2, GameCard::RocketBooster])
+///     .space(0, [SpaceCard::BlankSpace])
+///     .phase(Phase::Actions)
+///     .space_deck([SpaceCard::Meteoroid])
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Or write it as text, one setting per line:
+///
+/// ```text
+/// players 3
+/// seed 7
+/// ship 4
+/// turn 1
+/// phase actions
+/// player 0: hand O2 O1*4 Tether; space BlankSpace SolarFlare
+/// player 2: dead; space Meteoroid
+/// deck TractorBeam ...
+/// discard O1 O1
+/// space deck Meteoroid WormHole ...
+/// ```
+///
+/// Piles are written top first. A deck ending in `...` has the rest of the cards shuffled in
+/// below, otherwise it must hold exactly the cards left over. Players without a hand get the
+/// starting hand, unless they are dead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub seed: u64,
+    pub rules: RuleSet,
+    pub players: Vec<ScenarioPlayer>,
+    pub whose_turn: PlayerReference,
+
+    /// Pickup starts the turn from the beginning, with a card to draw.
+    pub phase: Phase,
+
+    pub deck: Pile<GameCard>,
+    pub discard_pile: Vec<GameCard>,
+    pub space_deck: Pile<SpaceCard>,
+    pub space_discard_pile: Vec<SpaceCard>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioPlayer {
+    pub alive: bool,
+    pub hand: Vec<GameCard>,
+    pub space: Vec<SpaceCard>,
+}
+
+/// The top of a deck, and whether anything goes below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pile<T> {
+    /// Drawn in this order.
+    pub top: Vec<T>,
+
+    /// Whether the top is the whole deck, rather than having the leftovers shuffled in below.
+    pub exact: bool,
+}
+
+impl<T> Default for Pile<T> {
+    fn default() -> Self {
+        Self {
+            top: Vec::new(),
+            exact: false,
+        }
+    }
+}
+
+impl Scenario {
+    /// Everybody alive with a starting hand and no space cards, at the start of player 0's first
+    /// turn, with the official rules.
+    pub fn new(player_count: usize) -> Self {
+        Self::with_rules(player_count, RuleSet::default())
+    }
+
+    pub fn with_rules(player_count: usize, rules: RuleSet) -> Self {
+        let hand: Vec<GameCard> = rules
+            .starting_hand()
+            .into_iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(card, count))
+            .collect();
+        let player = ScenarioPlayer {
+            alive: true,
+            hand,
+            space: Vec::new(),
+        };
+        Self {
+            seed: 0,
+            rules,
+            players: vec![player; player_count],
+            whose_turn: PlayerReference(0),
+            phase: Phase::Pickup,
+            deck: Pile::default(),
+            discard_pile: Vec::new(),
+            space_deck: Pile::default(),
+            space_discard_pile: Vec::new(),
+        }
+    }
+
+    /// Read a scenario written as text, naming the source in any error. The text only sets the
+    /// ship distance, so set `rules` afterwards to play it with a variant.
+    pub fn parse(name: &str, text: &str) -> Result<Self, ScenarioError> {
+        Parser::new(name, text).parse()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> miette::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Reading {}", path.display()))?;
+        Ok(Self::parse(&path.display().to_string(), &text)?)
+    }
+
+    /// Shuffles the leftover cards, and is the seed of the game from there on.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn ship_distance(mut self, ship_distance: usize) -> Self {
+        self.rules.ship_distance = Some(ship_distance);
+        self
+    }
+
+    /// Panics if there is no such player, as do the other methods taking a player.
+    pub fn hand(mut self, player: usize, cards: impl IntoIterator<Item = GameCard>) -> Self {
+        self.player_mut(player).hand = cards.into_iter().collect();
+        self
+    }
+
+    /// The player's space row, from the first card they travelled to.
+    pub fn space(mut self, player: usize, cards: impl IntoIterator<Item = SpaceCard>) -> Self {
+        self.player_mut(player).space = cards.into_iter().collect();
+        self
+    }
+
+    /// The player is dead, and holds no cards.
+    pub fn dead(mut self, player: usize) -> Self {
+        let player = self.player_mut(player);
+        player.alive = false;
+        player.hand.clear();
+        self
+    }
+
+    pub fn turn(mut self, player: usize) -> Self {
+        self.player_mut(player);
+        self.whose_turn = PlayerReference(player);
+        self
+    }
+
+    pub fn phase(mut self, phase: Phase) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// The next cards to draw, with the rest shuffled in below.
+    pub fn deck(mut self, top: impl IntoIterator<Item = GameCard>) -> Self {
+        self.deck = Pile {
+            top: top.into_iter().collect(),
+            exact: false,
+        };
+        self
+    }
+
+    /// Every card left to draw, in order.
+    pub fn exact_deck(mut self, cards: impl IntoIterator<Item = GameCard>) -> Self {
+        self.deck = Pile {
+            top: cards.into_iter().collect(),
+            exact: true,
+        };
+        self
+    }
+
+    pub fn discard_pile(mut self, cards: impl IntoIterator<Item = GameCard>) -> Self {
+        self.discard_pile = cards.into_iter().collect();
+        self
+    }
+
+    /// The next space cards to draw, with the rest shuffled in below.
+    pub fn space_deck(mut self, top: impl IntoIterator<Item = SpaceCard>) -> Self {
+        self.space_deck = Pile {
+            top: top.into_iter().collect(),
+            exact: false,
+        };
+        self
+    }
+
+    /// Every space card left to draw, in order.
+    pub fn exact_space_deck(mut self, cards: impl IntoIterator<Item = SpaceCard>) -> Self {
+        self.space_deck = Pile {
+            top: cards.into_iter().collect(),
+            exact: true,
+        };
+        self
+    }
+
+    pub fn space_discard_pile(mut self, cards: impl IntoIterator<Item = SpaceCard>) -> Self {
+        self.space_discard_pile = cards.into_iter().collect();
+        self
+    }
+
+    fn player_mut(&mut self, player: usize) -> &mut ScenarioPlayer {
+        let player_count = self.players.len();
+        self.players.get_mut(player).unwrap_or_else(|| {
+            panic!(
+                "There is no player {} in a scenario for {}.",
+                player, player_count
+            )
+        })
+    }
+
+    /// Check the scenario and set up the game. Call `start` on it to play from the position.
+    pub fn build(&self) -> Result<GameState, ScenarioError> {
+        let player_count = self.players.len();
+        self.rules.check(player_count)?;
+
+        if self.whose_turn.0 >= player_count {
+            return Err(ScenarioError::NoSuchPlayer(self.whose_turn.0));
+        }
+        let ship_distance = self.rules.ship_distance(player_count);
+        for (idx, player) in self.players.iter().enumerate() {
+            if !player.alive && !player.hand.is_empty() {
+                return Err(ScenarioError::DeadPlayerHoldsCards(idx));
+            }
+            if player.space.len() >= ship_distance {
+                return Err(ScenarioError::AlreadyAtShip(idx));
+            }
+        }
+        if self.players.iter().filter(|player| player.alive).count() < 2 {
+            return Err(ScenarioError::NotEnoughPlayersAlive);
+        }
+        if self.phase != Phase::Pickup && !self.players[self.whose_turn.0].alive {
+            return Err(ScenarioError::TurnOfDeadPlayer(self.whose_turn.0));
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let placed = self
+            .players
+            .iter()
+            .flat_map(|player| &player.hand)
+            .chain(&self.discard_pile);
+        let available = deck_pile(
+            "deck",
+            &self.rules.decks.game_cards,
+            placed,
+            &self.deck,
+            &mut rng,
+        )?;
+        let placed = self
+            .players
+            .iter()
+            .flat_map(|player| &player.space)
+            .chain(&self.space_discard_pile);
+        let space_cards = deck_pile(
+            "space deck",
+            &self.rules.decks.space_cards,
+            placed,
+            &self.space_deck,
+            &mut rng,
+        )?;
+
+        let players = self
+            .players
+            .iter()
+            .map(|player| Player {
+                alive: player.alive,
+                hand: player.hand.clone(),
+                space: player.space.clone(),
+            })
+            .collect();
+        Ok(GameState::from_position(Position {
+            seed: self.seed,
+            rng,
+            rules: self.rules.clone(),
+            players,
+            whose_turn: self.whose_turn,
+            phase: self.phase,
+            game_deck: GameDeck::from_parts(available, self.discard_pile.clone()),
+            space_deck: SpaceDeck::from_parts(space_cards, self.space_discard_pile.clone()),
+        }))
+    }
+}
+
+/// Everything `Scenario::build` works out, for `GameState` to put together.
+pub(crate) struct Position {
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+    pub rules: RuleSet,
+    pub players: Vec<Player>,
+    pub whose_turn: PlayerReference,
+    pub phase: Phase,
+    pub game_deck: GameDeck,
+    pub space_deck: SpaceDeck,
+}
+
+/// The cards left to draw, with the next one last, from whatever the rest of the scenario
+/// hasn't placed.
+fn deck_pile<'a, T: Ord + Clone + Debug + 'a>(
+    pile: &'static str,
+    decks: &BTreeMap<T, usize>,
+    placed: impl Iterator<Item = &'a T>,
+    top: &'a Pile<T>,
+    rng: &mut ChaCha8Rng,
+) -> Result<Vec<T>, ScenarioError> {
+    let placed: Vec<&T> = placed.chain(&top.top).collect();
+    let mut left = decks.clone();
+    for card in &placed {
+        let count = left.entry((*card).clone()).or_default();
+        if *count == 0 {
+            return Err(ScenarioError::TooManyCards {
+                card: format!("{:?}", card),
+                placed: placed.iter().filter(|c| **c == *card).count(),
+                available: decks.get(*card).copied().unwrap_or_default(),
+            });
+        }
+        *count -= 1;
+    }
+
+    let mut cards: Vec<T> = left
+        .iter()
+        .flat_map(|(card, count)| std::iter::repeat_n(card.clone(), *count))
+        .collect();
+    if top.exact {
+        if let Some((card, count)) = left.iter().find(|(_, count)| **count > 0) {
+            return Err(ScenarioError::LeftOverCards {
+                pile,
+                card: format!("{:?}", card),
+                count: *count,
+            });
+        }
+    }
+    cards.shuffle(rng);
+    cards.extend(top.top.iter().rev().cloned());
+    Ok(cards)
+}
+
+impl Display for Scenario {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "players {}", self.players.len())?;
+        writeln!(f, "seed {}", self.seed)?;
+        if let Some(ship_distance) = self.rules.ship_distance {
+            writeln!(f, "ship {}", ship_distance)?;
+        }
+        writeln!(f, "turn {}", self.whose_turn.0)?;
+        writeln!(f, "phase {}", phase_name(self.phase))?;
+        for (idx, player) in self.players.iter().enumerate() {
+            write!(f, "player {}:", idx)?;
+            if player.alive {
+                write!(f, " hand{}", cards(&player.hand))?;
+            } else {
+                write!(f, " dead")?;
+            }
+            writeln!(f, "; space{}", cards(&player.space))?;
+        }
+        let ending = |pile_exact: bool| if pile_exact { "" } else { " ..." };
+        writeln!(
+            f,
+            "deck{}{}",
+            cards(&self.deck.top),
+            ending(self.deck.exact)
+        )?;
+        writeln!(f, "discard{}", cards(&self.discard_pile))?;
+        writeln!(
+            f,
+            "space deck{}{}",
+            cards(&self.space_deck.top),
+            ending(self.space_deck.exact)
+        )?;
+        writeln!(f, "space discard{}", cards(&self.space_discard_pile))
+    }
+}
+
+/// Each card with a space in front, with repeats written like `O1*4`.
+fn cards<T: Debug + PartialEq>(cards: &[T]) -> String {
+    let mut text = String::new();
+    let mut idx = 0;
+    while idx < cards.len() {
+        let run = cards[idx..]
+            .iter()
+            .take_while(|card| **card == cards[idx])
+            .count();
+        text.push_str(&format!(" {:?}", cards[idx]));
+        if run > 1 {
+            text.push_str(&format!("*{}", run));
+        }
+        idx += run;
+    }
+    text
+}
+
+fn phase_name(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Pickup => "pickup",
+        Phase::Actions => "actions",
+        Phase::BreatheOrTravel => "breathe",
+    }
+}
+
+struct Parser<'a> {
+    name: &'a str,
+    text: &'a str,
+    /// Where the line being parsed starts, and its number counting from 1.
+    offset: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(name: &'a str, text: &'a str) -> Self {
+        Self {
+            name,
+            text,
+            offset: 0,
+            line: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Scenario, ScenarioError> {
+        let mut scenario: Option<Scenario> = None;
+        let mut offset = 0;
+        for (idx, raw) in self.text.split('\n').enumerate() {
+            self.offset = offset;
+            self.line = idx + 1;
+            offset += raw.len() + 1;
+
+            let line = raw.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+
+            if key == "players" {
+                if scenario.is_some() {
+                    return Err(self.error(raw, "players can only be given once"));
+                }
+                let player_count = self.number(raw, rest)?;
+                scenario = Some(Scenario::new(player_count));
+                continue;
+            }
+            let Some(scenario) = scenario.as_mut() else {
+                return Err(self.error(raw, "the first line must say how many players there are"));
+            };
+
+            match key {
+                "seed" => scenario.seed = self.number(raw, rest)? as u64,
+                "ship" => scenario.rules.ship_distance = Some(self.number(raw, rest)?),
+                "turn" => scenario.whose_turn = PlayerReference(self.player(raw, rest, scenario)?),
+                "phase" => {
+                    scenario.phase = match rest {
+                        "pickup" => Phase::Pickup,
+                        "actions" => Phase::Actions,
+                        "breathe" => Phase::BreatheOrTravel,
+                        _ => {
+                            return Err(
+                                self.error(raw, "the phase must be pickup, actions or breathe")
+                            )
+                        }
+                    }
+                }
+                "player" => self.player_line(raw, rest, scenario)?,
+                "deck" => scenario.deck = self.pile(raw, rest)?,
+                "discard" => scenario.discard_pile = self.cards(raw, rest)?,
+                "space" => match rest.split_once(' ').unwrap_or((rest, "")) {
+                    ("deck", cards) => scenario.space_deck = self.pile(raw, cards)?,
+                    ("discard", cards) => scenario.space_discard_pile = self.cards(raw, cards)?,
+                    _ => return Err(self.error(raw, "expected space deck or space discard")),
+                },
+                _ => return Err(self.error(raw, &format!("unknown setting {}", key))),
+            }
+        }
+
+        scenario.ok_or_else(|| {
+            self.offset = 0;
+            self.line = 1;
+            self.error("", "the scenario is empty")
+        })
+    }
+
+    /// `player 1: hand O2 O1*4; space BlankSpace`, or `player 2: dead; space Meteoroid`.
+    fn player_line(
+        &self,
+        raw: &str,
+        rest: &str,
+        scenario: &mut Scenario,
+    ) -> Result<(), ScenarioError> {
+        let Some((player, sections)) = rest.split_once(':') else {
+            return Err(self.error(raw, "expected a colon after the player"));
+        };
+        let idx = self.player(raw, player.trim(), scenario)?;
+        for section in sections.split(';') {
+            let section = section.trim();
+            let (key, cards) = section.split_once(' ').unwrap_or((section, ""));
+            let player = &mut scenario.players[idx];
+            match key {
+                "" => {}
+                "dead" => {
+                    player.alive = false;
+                    player.hand.clear();
+                }
+                "hand" => player.hand = self.cards(raw, cards)?,
+                "space" => player.space = self.cards(raw, cards)?,
+                _ => return Err(self.error(raw, "expected hand, space or dead")),
+            }
+        }
+        Ok(())
+    }
+
+    fn pile<T: DeserializeOwned + Clone>(
+        &self,
+        raw: &str,
+        text: &str,
+    ) -> Result<Pile<T>, ScenarioError> {
+        let (text, exact) = match text.strip_suffix("...") {
+            Some(text) => (text, false),
+            None => (text, true),
+        };
+        Ok(Pile {
+            top: self.cards(raw, text)?,
+            exact,
+        })
+    }
+
+    fn cards<T: DeserializeOwned + Clone>(
+        &self,
+        raw: &str,
+        text: &str,
+    ) -> Result<Vec<T>, ScenarioError> {
+        let mut cards = Vec::new();
+        for word in text.split_whitespace() {
+            let (name, count) = match word.split_once('*') {
+                Some((name, count)) => (name, self.number(raw, count)?),
+                None => (word, 1),
+            };
+            let card: T = T::deserialize(
+                IntoDeserializer::<serde::de::value::Error>::into_deserializer(name),
+            )
+            .map_err(|err| self.error(raw, &err.to_string()))?;
+            cards.extend(std::iter::repeat_n(card, count));
+        }
+        Ok(cards)
+    }
+
+    fn player(&self, raw: &str, text: &str, scenario: &Scenario) -> Result<usize, ScenarioError> {
+        let player = self.number(raw, text)?;
+        if player >= scenario.players.len() {
+            return Err(self.error(raw, &format!("there is no player {}", player)));
+        }
+        Ok(player)
+    }
+
+    fn number(&self, raw: &str, text: &str) -> Result<usize, ScenarioError> {
+        text.trim()
+            .parse()
+            .map_err(|_| self.error(raw, &format!("expected a number, not {:?}", text)))
+    }
+
+    fn error(&self, raw: &str, message: &str) -> ScenarioError {
+        ScenarioError::Parse {
+            name: self.name.to_string(),
+            line: self.line,
+            message: message.to_string(),
+            source_code: NamedSource::new(self.name, self.text.to_string()),
+            span: (self.offset, raw.trim_end().len()).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Decision, DeckConfig, PendingDecision};
+
+    const PUZZLE: &str = "
+        # Player 1 is one step from the ship.
+        players 3
+        seed 7
+        ship 4
+        turn 1
+        phase actions
+        player 0: hand O2 O1*4 Tether; space BlankSpace SolarFlare
+        player 1: hand O2 O1; space BlankSpace*3
+        player 2: dead; space Meteoroid
+        deck TractorBeam HackSuit ...
+        discard O1 O1
+        space deck Hyperspace ...
+    ";
+
+    #[test]
+    fn test_parse() {
+        let scenario = Scenario::parse("puzzle", PUZZLE).unwrap();
+        let expected = Scenario::new(3)
+            .seed(7)
+            .ship_distance(4)
+            .turn(1)
+            .phase(Phase::Actions)
+            .hand(
+                0,
+                [
+                    GameCard::O2,
+                    GameCard::O1,
+                    GameCard::O1,
+                    GameCard::O1,
+                    GameCard::O1,
+                    GameCard::Tether,
+                ],
+            )
+            .space(0, [SpaceCard::BlankSpace, SpaceCard::SolarFlare])
+            .hand(1, [GameCard::O2, GameCard::O1])
+            .space(1, vec![SpaceCard::BlankSpace; 3])
+            .dead(2)
+            .space(2, [SpaceCard::Meteoroid])
+            .deck([GameCard::TractorBeam, GameCard::HackSuit])
+            .discard_pile([GameCard::O1, GameCard::O1])
+            .space_deck([SpaceCard::Hyperspace]);
+        assert_eq!(scenario, expected);
+        assert_eq!(
+            Scenario::parse("copy", &scenario.to_string()).unwrap(),
+            scenario
+        );
+    }
+
+    #[test]
+    fn test_play_from_scenario() {
+        let mut state = Scenario::parse("puzzle", PUZZLE).unwrap().build().unwrap();
+        state.set_check_invariants(true);
+        assert_eq!(state.check_invariants(), Ok(()));
+        state.start().unwrap();
+        assert_eq!(state.whose_turn(), PlayerReference(1));
+        assert_eq!(state.discard_pile(), &[GameCard::O1, GameCard::O1]);
+
+        state
+            .apply(Decision::BreatheOrTravel(crate::BreatheOrTravel::Travel))
+            .unwrap_err();
+        state.apply(Decision::PlayAction(None)).unwrap();
+        state
+            .apply(Decision::BreatheOrTravel(crate::BreatheOrTravel::Travel))
+            .unwrap();
+        assert_eq!(state.winner(), Some(PlayerReference(1)));
+    }
+
+    #[test]
+    fn test_deck_order() {
+        let mut state = Scenario::new(2)
+            .deck([GameCard::Shield, GameCard::Tether])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        assert_eq!(
+            state.player(&PlayerReference(0)).unwrap().hand.last(),
+            Some(&GameCard::Shield)
+        );
+        state.apply(Decision::PlayAction(None)).unwrap();
+        state
+            .apply(Decision::BreatheOrTravel(crate::BreatheOrTravel::Breathe))
+            .unwrap();
+        assert_eq!(
+            state.player(&PlayerReference(1)).unwrap().hand.last(),
+            Some(&GameCard::Tether)
+        );
+    }
+
+    #[test]
+    fn test_empty_deck() {
+        let cards = DeckConfig::base().game_deck();
+        let (first, second) = cards.split_at(cards.len() / 2);
+        let mut state = Scenario::new(2)
+            .hand(0, first.to_vec())
+            .hand(1, second.to_vec())
+            .exact_deck([])
+            .build()
+            .unwrap();
+        state.set_check_invariants(true);
+        state.start().unwrap();
+        assert_eq!(
+            state.player(&PlayerReference(0)).unwrap().hand.len(),
+            first.len()
+        );
+        assert_eq!(
+            state.pending_decision(),
+            Some(&PendingDecision::PlayAction {
+                player: PlayerReference(0)
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_scenarios() {
+        assert!(matches!(
+            Scenario::new(2).hand(0, [GameCard::Shield; 5]).build(),
+            Err(ScenarioError::TooManyCards {
+                placed: 5,
+                available: 4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Scenario::new(2)
+                .hand(0, [GameCard::Shield; 3])
+                .hand(1, [GameCard::Shield; 3])
+                .build(),
+            Err(ScenarioError::TooManyCards {
+                placed: 6,
+                available: 4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Scenario::new(2).exact_deck([GameCard::Shield]).build(),
+            Err(ScenarioError::LeftOverCards { pile: "deck", .. })
+        ));
+        assert!(matches!(
+            Scenario::new(3).dead(0).dead(1).build(),
+            Err(ScenarioError::NotEnoughPlayersAlive)
+        ));
+        assert!(matches!(
+            Scenario::new(3)
+                .dead(1)
+                .turn(1)
+                .phase(Phase::Actions)
+                .build(),
+            Err(ScenarioError::TurnOfDeadPlayer(1))
+        ));
+        assert!(matches!(
+            Scenario::new(2)
+                .ship_distance(1)
+                .space(1, [SpaceCard::BlankSpace])
+                .build(),
+            Err(ScenarioError::AlreadyAtShip(1))
+        ));
+
+        let Err(ScenarioError::Parse { line, span, .. }) =
+            Scenario::parse("bad", "players 2\nplayer 0: hand O3")
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(line, 2);
+        assert_eq!(span.offset(), 10);
+    }
+}
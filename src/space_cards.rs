@@ -1,7 +1,9 @@
+use crate::DeckConfig;
 use rand::prelude::SliceRandom;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum SpaceCard {
     BlankSpace,
     UsefulJunk,
@@ -15,56 +17,57 @@ pub enum SpaceCard {
     SolarFlare,
 }
 
-pub struct SpaceDeck(Vec<SpaceCard>);
+#[derive(Clone)]
+pub struct SpaceDeck {
+    cards: Vec<SpaceCard>,
+    /// Cards knocked out of space rows, shuffled back in when the deck runs out.
+    discard: Vec<SpaceCard>,
+}
 
 impl SpaceDeck {
-    pub fn new() -> Self {
-        let mut cards = Vec::new();
-
-        for _ in 0..9 {
-            cards.push(SpaceCard::BlankSpace);
-        }
-        for _ in 0..5 {
-            cards.push(SpaceCard::UsefulJunk);
-        }
-        for _ in 0..2 {
-            cards.push(SpaceCard::MysteriousNebula);
-        }
-        for _ in 0..1 {
-            cards.push(SpaceCard::Hyperspace);
-        }
-        for _ in 0..4 {
-            cards.push(SpaceCard::Meteoroid);
-        }
-        for _ in 0..6 {
-            cards.push(SpaceCard::CosmicRadiation);
-        }
-        for _ in 0..2 {
-            cards.push(SpaceCard::AsteroidField);
-        }
-        for _ in 0..4 {
-            cards.push(SpaceCard::GravitationalAnomaly);
-        }
-        for _ in 0..4 {
-            cards.push(SpaceCard::WormHole);
-        }
-        for _ in 0..5 {
-            cards.push(SpaceCard::SolarFlare);
+    pub fn new(decks: &DeckConfig) -> Self {
+        Self {
+            cards: decks.space_deck(),
+            discard: Vec::new(),
         }
+    }
 
-        Self(cards)
+    /// A deck part way through a game, with the next card to draw last.
+    pub(crate) fn from_parts(cards: Vec<SpaceCard>, discard: Vec<SpaceCard>) -> Self {
+        Self { cards, discard }
     }
 
-    pub fn shuffled<R: Rng>(rng: &mut R) -> Self {
-        let mut deck = Self::new();
-        deck.0.shuffle(rng);
+    pub fn shuffled<R: Rng>(decks: &DeckConfig, rng: &mut R) -> Self {
+        let mut deck = Self::new(decks);
+        deck.shuffle(rng);
         deck
     }
 
-    pub fn draw(&mut self) -> SpaceCard {
-        if self.0.is_empty() {
-            *self = Self::new();
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    /// If there are no cards left, shuffle the discard pile back in.
+    ///
+    /// Returns None when every card is in a space row, which `RuleSet::check` rules out by
+    /// making sure the deck is big enough for every player to get within one card of the ship.
+    pub fn draw<R: Rng>(&mut self, rng: &mut R) -> Option<SpaceCard> {
+        if self.cards.is_empty() {
+            self.cards.append(&mut self.discard);
+            self.cards.shuffle(rng);
         }
-        self.0.pop().unwrap()
+        self.cards.pop()
+    }
+
+    pub fn cards(&self) -> &[SpaceCard] {
+        &self.cards
+    }
+
+    pub fn discard_pile(&self) -> &[SpaceCard] {
+        &self.discard
+    }
+
+    pub fn add_to_discard(&mut self, card: SpaceCard) {
+        self.discard.push(card);
     }
 }
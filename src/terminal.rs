@@ -0,0 +1,81 @@
+use crate::events::{GameEvent, GameObserver};
+use crate::{Game, PlayerReference};
+use owo_colors::{CssColors, DynColors, OwoColorize};
+
+/// Each seat's colour, shared by everything that draws the game.
+pub(crate) const PLAYER_COLORS: [(u8, u8, u8); 6] = [
+    (0xB8, 0x3A, 0xF1),
+    (0x6E, 0xB1, 0x22),
+    (0xDA, 0xAC, 0x06),
+    (0x00, 0x93, 0x8A),
+    (0xE2, 0x38, 0x38),
+    (0xA2, 0x34, 0x50),
+];
+
+/// Prints every event in the acting player's colour, and everyone's state after each step.
+pub struct TerminalObserver {
+    show_board: bool,
+}
+
+impl TerminalObserver {
+    pub fn new() -> Self {
+        Self { show_board: true }
+    }
+
+    /// Only print the events, e.g. when a person is playing and shouldn't see every hand.
+    pub fn events_only() -> Self {
+        Self { show_board: false }
+    }
+
+    pub fn color(player_reference: &PlayerReference) -> DynColors {
+        let (r, g, b) = PLAYER_COLORS[player_reference.0 % PLAYER_COLORS.len()];
+        DynColors::Rgb(r, g, b)
+    }
+
+    pub fn print(game: &Game) {
+        for idx in 0..game.player_count() {
+            let player_reference = PlayerReference(idx);
+            let player = match game.player(&player_reference) {
+                Ok(player) => player,
+                Err(_) => continue,
+            };
+            let is_turn = game.whose_turn() == player_reference;
+            let prefix = if !player.alive {
+                " x "
+            } else if is_turn {
+                "-->"
+            } else {
+                "   "
+            };
+
+            let color = if !player.alive {
+                CssColors::OrangeRed
+            } else if is_turn {
+                CssColors::White
+            } else {
+                CssColors::Grey
+            };
+            println!(" {} {:?}", prefix, player.color(color));
+        }
+    }
+}
+
+impl Default for TerminalObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameObserver for TerminalObserver {
+    fn on_event(&mut self, game: &Game, event: &GameEvent) {
+        let note = event.to_string();
+        let player_reference = event.player().unwrap_or_else(|| game.whose_turn());
+        println!("\n{}", note.color(Self::color(&player_reference)));
+    }
+
+    fn on_step(&mut self, game: &Game) {
+        if self.show_board {
+            Self::print(game);
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use crate::actions::BreatheOrTravel;
+use crate::visible_state::VisibleState;
+use crate::{Action, GameCard, PlayerController, PlayerReference};
+use miette::{Diagnostic, Report};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{BufRead, Stdin, Stdout, Write};
+use thiserror::Error;
+
+/// A menu answer that can't be used.
+#[derive(Error, Debug, Diagnostic)]
+pub enum MenuError {
+    #[error("{input:?} isn't a number.")]
+    #[diagnostic(help("Type the number next to your choice and press enter."))]
+    NotANumber { input: String },
+
+    #[error("There is no option {choice}.")]
+    #[diagnostic(help("Pick a number from 1 to {options}."))]
+    NoSuchOption { choice: usize, options: usize },
+}
+
+/// Lets a person play a seat by answering numbered menus.
+///
+/// Only legal choices are ever offered, and bad input is explained and asked again, so a typo
+/// never costs a turn. If the input runs out, the first option is picked from then on.
+pub struct TerminalPlayerController<R, W> {
+    input: R,
+    output: W,
+    visible_state: VisibleState,
+}
+
+impl TerminalPlayerController<std::io::StdinLock<'static>, Stdout> {
+    /// Reads from stdin and writes to stdout.
+    pub fn stdio() -> Self {
+        let stdin: Stdin = std::io::stdin();
+        Self::new(stdin.lock(), std::io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> TerminalPlayerController<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            visible_state: VisibleState::invalid(),
+        }
+    }
+
+    /// The terminal is only for show, so failing to write to it isn't worth stopping the game.
+    fn say(&mut self, text: impl Display) {
+        let _ = writeln!(self.output, "{}", text);
+    }
+
+    fn show_state(&mut self) {
+        let state = self.visible_state.clone();
+        self.say("");
+        self.say(format!("The ship is {} spaces away.", state.ship_distance));
+        for (idx, player) in state.players.iter().enumerate() {
+            let who = if idx == state.viewer.0 {
+                "you".to_string()
+            } else {
+                format!("{} cards", player.hand_size)
+            };
+            let status = if player.alive { "" } else { ", dead" };
+            self.say(format!(
+                "  Player {} ({}{}): {:?}",
+                idx, who, status, player.space
+            ));
+        }
+        self.say(format!("Your hand: {:?}", state.my_hand));
+    }
+
+    /// Ask until one of the options is picked, returning its index.
+    fn choose(&mut self, prompt: &str, options: &[String]) -> usize {
+        if options.len() <= 1 {
+            return 0;
+        }
+
+        loop {
+            self.say(prompt);
+            for (idx, option) in options.iter().enumerate() {
+                self.say(format!("  {}) {}", idx + 1, option));
+            }
+            let _ = write!(self.output, "> ");
+            let _ = self.output.flush();
+
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    self.say("");
+                    return 0;
+                }
+                Ok(_) => {}
+            }
+
+            match parse_choice(line.trim(), options.len()) {
+                Ok(idx) => return idx,
+                Err(err) => self.say(format!("{:?}", Report::new(err))),
+            }
+        }
+    }
+}
+
+fn parse_choice(input: &str, options: usize) -> Result<usize, MenuError> {
+    let choice: usize = input.parse().map_err(|_| MenuError::NotANumber {
+        input: input.to_string(),
+    })?;
+    if choice == 0 || choice > options {
+        return Err(MenuError::NoSuchOption { choice, options });
+    }
+    Ok(choice - 1)
+}
+
+impl<R: BufRead, W: Write> PlayerController for TerminalPlayerController<R, W> {
+    fn update_state(&mut self, visible_state: VisibleState) {
+        self.visible_state = visible_state;
+    }
+
+    fn play_action(&mut self) -> Option<Action> {
+        let actions = self.visible_state.legal_actions();
+        if actions.is_empty() {
+            return None;
+        }
+
+        self.show_state();
+        let mut options = vec!["Nothing, move on to breathing or travelling".to_string()];
+        options.extend(actions.iter().map(|action| action.to_string()));
+        match self.choose("Play an action card?", &options) {
+            0 => None,
+            idx => Some(actions[idx - 1].clone()),
+        }
+    }
+
+    fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+        let choices = self.visible_state.legal_breathe_or_travel();
+        self.show_state();
+        let options: Vec<String> = choices
+            .iter()
+            .map(|choice| match choice {
+                BreatheOrTravel::Breathe => "Breathe, using an O1".to_string(),
+                BreatheOrTravel::Travel => "Travel, using an O2".to_string(),
+            })
+            .collect();
+        let idx = self.choose("Breathe or travel?", &options);
+        choices
+            .get(idx)
+            .copied()
+            .unwrap_or(BreatheOrTravel::Breathe)
+    }
+
+    fn defend(&mut self, action: &Action) -> bool {
+        let options = vec!["Yes".to_string(), "No".to_string()];
+        let prompt = format!(
+            "You are being attacked with {:?}. Use a shield?",
+            action.card()
+        );
+        self.choose(&prompt, &options) == 0
+    }
+
+    fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+        let discards = self.visible_state.legal_discards(card_count);
+        self.show_state();
+        let options: Vec<String> = discards
+            .iter()
+            .map(|cards| format!("{:?}", cards))
+            .collect();
+        let prompt = format!("A meteoroid hit you. Discard {} cards:", card_count);
+        let idx = self.choose(&prompt, &options);
+        discards.get(idx).cloned().unwrap_or_default()
+    }
+
+    fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+        let targets = self.visible_state.legal_swap_targets();
+        self.show_state();
+        let options: Vec<String> = targets
+            .iter()
+            .map(|target| {
+                let space = &self.visible_state.players[target.0].space;
+                format!("Player {}: {:?}", target.0, space)
+            })
+            .collect();
+        let idx = self.choose("A wormhole! Swap spaces with:", &options);
+        targets
+            .get(idx)
+            .copied()
+            .unwrap_or(self.visible_state.viewer)
+    }
+
+    fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+        let mut cards: Vec<GameCard> = options.into_iter().collect();
+        cards.sort_by_key(|card| *card as usize);
+        let labels: Vec<String> = cards.iter().map(|card| format!("{:?}", card)).collect();
+        let idx = self.choose("Take which card?", &labels);
+        cards[idx]
+    }
+
+    fn pay_oxygen(&mut self, cost: usize, options: Vec<Vec<GameCard>>) -> Vec<GameCard> {
+        self.show_state();
+        let labels: Vec<String> = options.iter().map(|cards| format!("{:?}", cards)).collect();
+        let prompt = format!("You need {} oxygen. Pay with:", cost);
+        let idx = self.choose(&prompt, &labels);
+        options.get(idx).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visible_state::visible_player as player;
+    use crate::SpaceCard;
+    use std::io::Cursor;
+
+    fn controller(input: &str) -> TerminalPlayerController<Cursor<Vec<u8>>, Vec<u8>> {
+        let mut controller =
+            TerminalPlayerController::new(Cursor::new(input.as_bytes().to_vec()), Vec::new());
+        controller.update_state(VisibleState {
+            viewer: PlayerReference(0),
+            whose_turn: PlayerReference(0),
+            ship_distance: 6,
+            rules: Default::default(),
+            my_hand: vec![GameCard::O1, GameCard::O2, GameCard::LaserBlast],
+            players: vec![
+                player(3, vec![]),
+                player(4, vec![SpaceCard::BlankSpace]),
+                player(5, vec![]),
+            ],
+            ..VisibleState::invalid()
+        });
+        controller
+    }
+
+    fn output(controller: &TerminalPlayerController<Cursor<Vec<u8>>, Vec<u8>>) -> String {
+        String::from_utf8_lossy(&controller.output).to_string()
+    }
+
+    #[test]
+    fn test_play_action() {
+        let mut controller = controller("2\n");
+        assert_eq!(
+            controller.play_action(),
+            Some(Action::LaserBlast {
+                target: PlayerReference(1)
+            })
+        );
+    }
+
+    #[test]
+    fn test_reprompts_bad_input() {
+        let mut controller = controller("blast\n7\n1\n");
+        assert_eq!(controller.play_action(), None);
+        let output = output(&controller);
+        assert!(output.contains("isn't a number"));
+        assert!(output.contains("There is no option 7"));
+        assert_eq!(output.matches("Play an action card?").count(), 3);
+    }
+
+    #[test]
+    fn test_breathe_or_travel() {
+        let mut controller = controller("2\n");
+        assert_eq!(controller.breathe_or_travel(), BreatheOrTravel::Travel);
+    }
+
+    #[test]
+    fn test_end_of_input_picks_first_option() {
+        let mut controller = controller("");
+        assert!(controller.defend(&Action::HoleInSuit {
+            target: PlayerReference(0)
+        }));
+        assert_eq!(controller.choose_player_to_swap_with(), PlayerReference(1));
+    }
+}
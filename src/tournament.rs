@@ -0,0 +1,645 @@
+use crate::{Game, GameState, MisconductPolicy, PlayerController, RuleError, RuleSet};
+use miette::Diagnostic;
+use rand::prelude::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Makes a fresh controller for one seat of one game, from a seed so that bots are reproducible.
+pub type ControllerFactory = Box<dyn Fn(u64) -> Box<dyn PlayerController> + Send + Sync>;
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum TournamentError {
+    #[error("A game needs between 2 and 6 players, not {0}.")]
+    PlayerCount(usize),
+
+    #[error("The tournament has no entrants.")]
+    #[diagnostic(help("Add some with Tournament::add_entrant."))]
+    NoEntrants,
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Rules(#[from] RuleError),
+}
+
+/// How a `Tournament` is played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentConfig {
+    /// Seats at each table.
+    pub players_per_game: usize,
+
+    /// Deals to play. Each deal is played once for every distinct seating of its lineup, so that
+    /// nobody benefits from a lucky seat or a lucky deck.
+    pub rounds: usize,
+
+    /// Everything, from lineups to deals to the bots' own dice, follows from this.
+    pub seed: u64,
+
+    /// Games to play at once. Defaults to the number of CPU cores.
+    pub threads: usize,
+
+    /// Resamples of the results used to put confidence intervals on the ratings.
+    pub bootstrap_samples: usize,
+
+    /// The rules, and cards, every game is played with.
+    pub rules: RuleSet,
+
+    /// What happens to entrants that answer with something the rules don't allow.
+    pub misconduct_policy: MisconductPolicy,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            players_per_game: 4,
+            rounds: 100,
+            seed: 0,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            bootstrap_samples: 200,
+            rules: RuleSet::default(),
+            misconduct_policy: MisconductPolicy::default(),
+        }
+    }
+}
+
+struct Entrant {
+    name: String,
+    factory: ControllerFactory,
+}
+
+/// Pits controllers against each other over many seeded games.
+///
+/// ```
+/// use selfish::{HeuristicPlayerController, RandomPlayerController, Tournament, TournamentConfig};
+///
+/// let mut tournament = Tournament::new(TournamentConfig {
+///     players_per_game: 2,
+///     rounds: 10,
+///     ..TournamentConfig::default()
+/// });
+/// tournament.add_entrant("random", |seed| Box::new(RandomPlayerController::with_seed(seed)));
+/// tournament.add_entrant("heuristic", |_| Box::new(HeuristicPlayerController::default()));
+///
+/// let report = tournament.run().unwrap();
+/// assert_eq!(report.games.len(), 20);
+/// println!("{}", report);
+/// ```
+pub struct Tournament {
+    config: TournamentConfig,
+    entrants: Vec<Entrant>,
+}
+
+impl Tournament {
+    pub fn new(config: TournamentConfig) -> Self {
+        Self {
+            config,
+            entrants: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &TournamentConfig {
+        &self.config
+    }
+
+    pub fn add_entrant<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn(u64) -> Box<dyn PlayerController> + Send + Sync + 'static,
+    {
+        self.entrants.push(Entrant {
+            name: name.into(),
+            factory: Box::new(factory),
+        });
+    }
+
+    /// Every game that will be played, in order.
+    ///
+    /// Each round draws a lineup from the entrants, repeating them only when there are fewer
+    /// entrants than seats, and plays one deal in every distinct seating of that lineup.
+    pub fn schedule(&self) -> Result<Vec<ScheduledGame>, TournamentError> {
+        let seats = self.config.players_per_game;
+        if !(2..=6).contains(&seats) {
+            return Err(TournamentError::PlayerCount(seats));
+        }
+        if self.entrants.is_empty() {
+            return Err(TournamentError::NoEntrants);
+        }
+        self.config.rules.check(seats)?;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.config.seed);
+        let mut games = Vec::new();
+        for round in 0..self.config.rounds {
+            let mut lineup = Vec::new();
+            while lineup.len() < seats {
+                let mut pool: Vec<usize> = (0..self.entrants.len()).collect();
+                pool.shuffle(&mut rng);
+                lineup.extend(pool.into_iter().take(seats - lineup.len()));
+            }
+            let seed = rng.gen();
+            for entrants in permutations(&lineup) {
+                games.push(ScheduledGame {
+                    round,
+                    seed,
+                    entrants,
+                });
+            }
+        }
+        Ok(games)
+    }
+
+    /// Play every scheduled game, spread over `threads`, and summarise the results.
+    pub fn run(&self) -> miette::Result<TournamentReport> {
+        let schedule = self.schedule()?;
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(schedule.len()));
+        let threads = self.config.threads.clamp(1, schedule.len().max(1));
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(scheduled) = schedule.get(idx) else {
+                        break;
+                    };
+                    let record = self.play(scheduled);
+                    results.lock().unwrap().push((idx, record));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(idx, _)| *idx);
+        let games = results.into_iter().map(|(_, record)| record).collect();
+        Ok(TournamentReport::new(
+            self.entrants.iter().map(|e| e.name.clone()).collect(),
+            self.config.players_per_game,
+            games,
+            self.config.bootstrap_samples,
+            self.config.seed,
+        ))
+    }
+
+    fn play(&self, scheduled: &ScheduledGame) -> GameRecord {
+        let controllers = scheduled
+            .entrants
+            .iter()
+            .enumerate()
+            .map(|(seat, entrant)| {
+                (self.entrants[*entrant].factory)(seat_seed(scheduled.seed, seat))
+            })
+            .collect();
+        // The schedule already checked the rules work for this many players.
+        let state = GameState::with_rules(
+            Some(scheduled.seed),
+            scheduled.entrants.len(),
+            self.config.rules.clone(),
+        )
+        .expect("rules were checked when scheduling");
+        let mut game = Game::from_state(state, controllers);
+        game.set_misconduct_policy(self.config.misconduct_policy);
+        let (winner, error) = match game.simulate() {
+            Ok(()) => (game.winner().map(|player| player.0), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        GameRecord {
+            round: scheduled.round,
+            seed: scheduled.seed,
+            entrants: scheduled.entrants.clone(),
+            winner,
+            error,
+        }
+    }
+}
+
+/// A game in a tournament's schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledGame {
+    pub round: usize,
+    pub seed: u64,
+    /// The entrant in each seat.
+    pub entrants: Vec<usize>,
+}
+
+/// How a tournament game went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameRecord {
+    pub round: usize,
+    pub seed: u64,
+    /// The entrant in each seat.
+    pub entrants: Vec<usize>,
+    /// The winning seat.
+    pub winner: Option<usize>,
+    /// Why the game was abandoned, if it was.
+    pub error: Option<String>,
+}
+
+impl GameRecord {
+    pub fn winning_entrant(&self) -> Option<usize> {
+        self.winner.map(|seat| self.entrants[seat])
+    }
+}
+
+/// A proportion with its 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Estimate {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntrantStats {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    pub win_rate: Estimate,
+    /// On the Elo scale, averaging 1500 across the entrants.
+    pub rating: Estimate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeatStats {
+    pub games: usize,
+    pub wins: usize,
+    pub win_rate: Estimate,
+}
+
+/// The results of a tournament.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TournamentReport {
+    pub entrants: Vec<EntrantStats>,
+    /// How often each seat won, whoever sat in it. With no seat bias these are all about
+    /// one over the number of seats.
+    pub seats: Vec<SeatStats>,
+    pub games: Vec<GameRecord>,
+    /// Games that ended in an error and were left out of the statistics.
+    pub failed: usize,
+}
+
+impl TournamentReport {
+    fn new(
+        names: Vec<String>,
+        players_per_game: usize,
+        games: Vec<GameRecord>,
+        bootstrap_samples: usize,
+        seed: u64,
+    ) -> Self {
+        let finished: Vec<&GameRecord> = games.iter().filter(|game| game.error.is_none()).collect();
+
+        let ratings = luce_ratings(names.len(), &finished);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut samples = vec![Vec::with_capacity(bootstrap_samples); names.len()];
+        for _ in 0..bootstrap_samples {
+            let resampled: Vec<&GameRecord> = (0..finished.len())
+                .map(|_| finished[rng.gen_range(0..finished.len())])
+                .collect();
+            for (entrant, rating) in luce_ratings(names.len(), &resampled)
+                .into_iter()
+                .enumerate()
+            {
+                samples[entrant].push(rating);
+            }
+        }
+
+        let entrants = names
+            .into_iter()
+            .enumerate()
+            .map(|(entrant, name)| {
+                let mut games = 0;
+                let mut wins = 0;
+                for game in &finished {
+                    let seats = game.entrants.iter().filter(|e| **e == entrant).count();
+                    games += seats;
+                    if game.winning_entrant() == Some(entrant) {
+                        wins += 1;
+                    }
+                }
+                EntrantStats {
+                    name,
+                    games,
+                    wins,
+                    win_rate: wilson(wins, games),
+                    rating: percentile_interval(ratings[entrant], &mut samples[entrant]),
+                }
+            })
+            .collect();
+
+        let seats = (0..players_per_game)
+            .map(|seat| {
+                let wins = finished
+                    .iter()
+                    .filter(|game| game.winner == Some(seat))
+                    .count();
+                SeatStats {
+                    games: finished.len(),
+                    wins,
+                    win_rate: wilson(wins, finished.len()),
+                }
+            })
+            .collect();
+
+        let failed = games.len() - finished.len();
+        Self {
+            entrants,
+            seats,
+            games,
+            failed,
+        }
+    }
+}
+
+impl Display for TournamentReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>6} {:>6} {:>22} {:>22}",
+            "Entrant", "Games", "Wins", "Win rate", "Rating"
+        )?;
+        for entrant in &self.entrants {
+            writeln!(
+                f,
+                "{:<20} {:>6} {:>6} {:>22} {:>22}",
+                entrant.name,
+                entrant.games,
+                entrant.wins,
+                format_percent(&entrant.win_rate),
+                format!(
+                    "{:.0} ({:.0}-{:.0})",
+                    entrant.rating.value, entrant.rating.low, entrant.rating.high
+                ),
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<20} {:>6} {:>6} {:>22}",
+            "Seat", "Games", "Wins", "Win rate"
+        )?;
+        for (idx, seat) in self.seats.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<20} {:>6} {:>6} {:>22}",
+                idx,
+                seat.games,
+                seat.wins,
+                format_percent(&seat.win_rate)
+            )?;
+        }
+
+        if self.failed > 0 {
+            writeln!(f)?;
+            writeln!(f, "{} games failed and were left out.", self.failed)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_percent(estimate: &Estimate) -> String {
+    format!(
+        "{:.1}% ({:.1}-{:.1})",
+        estimate.value * 100.0,
+        estimate.low * 100.0,
+        estimate.high * 100.0
+    )
+}
+
+/// Spreads the seats of a game over the seed space so no two seats share dice. Seat N of a game
+/// played with the same seed outside a tournament gets the same seed from this.
+pub fn seat_seed(game_seed: u64, seat: usize) -> u64 {
+    game_seed ^ (seat as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Every distinct ordering of the lineup, in lexicographic order.
+fn permutations(lineup: &[usize]) -> Vec<Vec<usize>> {
+    let mut current = lineup.to_vec();
+    current.sort_unstable();
+    let mut all = vec![current.clone()];
+    // The next permutation algorithm, which skips repeats when entrants appear more than once.
+    loop {
+        let Some(pivot) = (1..current.len())
+            .rev()
+            .find(|&i| current[i - 1] < current[i])
+        else {
+            return all;
+        };
+        let swap = (pivot..current.len())
+            .rev()
+            .find(|&i| current[i] > current[pivot - 1])
+            .unwrap();
+        current.swap(pivot - 1, swap);
+        current[pivot..].reverse();
+        all.push(current.clone());
+    }
+}
+
+/// The Wilson score interval, which behaves well for small samples and rates near 0 or 1.
+fn wilson(wins: usize, games: usize) -> Estimate {
+    if games == 0 {
+        return Estimate {
+            value: 0.0,
+            low: 0.0,
+            high: 1.0,
+        };
+    }
+
+    const Z: f64 = 1.96;
+    let n = games as f64;
+    let p = wins as f64 / n;
+    let centre = (p + Z * Z / (2.0 * n)) / (1.0 + Z * Z / n);
+    let spread = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / (1.0 + Z * Z / n);
+    Estimate {
+        value: p,
+        low: (centre - spread).max(0.0),
+        high: (centre + spread).min(1.0),
+    }
+}
+
+fn percentile_interval(value: f64, samples: &mut [f64]) -> Estimate {
+    if samples.is_empty() {
+        return Estimate {
+            value,
+            low: value,
+            high: value,
+        };
+    }
+
+    samples.sort_by(f64::total_cmp);
+    let at = |q: f64| samples[((samples.len() - 1) as f64 * q).round() as usize];
+    Estimate {
+        value,
+        low: at(0.025),
+        high: at(0.975),
+    }
+}
+
+/// Elo-scale ratings from a Luce choice model, where each entrant at the table wins in proportion
+/// to their strength.
+///
+/// Unlike updating Elo game by game, this doesn't depend on the order games finished in. The
+/// strengths are fitted with the minorize-maximize iteration, with every entrant given a win and
+/// a loss against an average opponent so that nobody is rated infinitely bad for never winning.
+fn luce_ratings(entrants: usize, games: &[&GameRecord]) -> Vec<f64> {
+    let mut strengths = vec![1.0; entrants];
+    let mut wins = vec![1.0; entrants];
+    for game in games {
+        if let Some(winner) = game.winning_entrant() {
+            wins[winner] += 1.0;
+        }
+    }
+
+    for _ in 0..200 {
+        let mut denominators: Vec<f64> = strengths.iter().map(|s| 2.0 / (s + 1.0)).collect();
+        for game in games {
+            let total: f64 = game.entrants.iter().map(|e| strengths[*e]).sum();
+            for entrant in &game.entrants {
+                denominators[*entrant] += 1.0 / total;
+            }
+        }
+
+        let mut change: f64 = 0.0;
+        for entrant in 0..entrants {
+            let updated = wins[entrant] / denominators[entrant];
+            change = change.max((updated / strengths[entrant]).ln().abs());
+            strengths[entrant] = updated;
+        }
+        if change < 1e-6 {
+            break;
+        }
+    }
+
+    let elos: Vec<f64> = strengths.iter().map(|s| 400.0 * s.log10()).collect();
+    let mean = elos.iter().sum::<f64>() / entrants as f64;
+    elos.iter().map(|elo| elo - mean + 1500.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HeuristicPlayerController, RandomPlayerController};
+
+    fn tournament(rounds: usize, threads: usize) -> Tournament {
+        let mut tournament = Tournament::new(TournamentConfig {
+            players_per_game: 3,
+            rounds,
+            seed: 7,
+            threads,
+            bootstrap_samples: 50,
+            ..TournamentConfig::default()
+        });
+        tournament.add_entrant("random", |seed| {
+            Box::new(RandomPlayerController::with_seed(seed))
+        });
+        tournament.add_entrant("heuristic", |_| {
+            Box::new(HeuristicPlayerController::default())
+        });
+        tournament
+    }
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(permutations(&[0, 1, 2]).len(), 6);
+        assert_eq!(
+            permutations(&[1, 0, 1]),
+            vec![vec![0, 1, 1], vec![1, 0, 1], vec![1, 1, 0]]
+        );
+    }
+
+    #[test]
+    fn test_schedule_covers_every_seating() {
+        let schedule = tournament(5, 1).schedule().unwrap();
+        // Two entrants at three seats means one of them sits twice, in three distinct seatings.
+        assert_eq!(schedule.len(), 15);
+        for round in schedule.chunks(3) {
+            assert!(round.iter().all(|game| game.seed == round[0].seed));
+            for seat in 0..3 {
+                let mut sitting: Vec<usize> =
+                    round.iter().map(|game| game.entrants[seat]).collect();
+                sitting.sort();
+                sitting.dedup();
+                assert_eq!(sitting.len(), 2, "both entrants sit in seat {}", seat);
+            }
+        }
+    }
+
+    #[test]
+    fn test_threads_do_not_change_results() {
+        let one = tournament(20, 1).run().unwrap();
+        let many = tournament(20, 4).run().unwrap();
+        assert_eq!(one, many);
+        assert_eq!(one.failed, 0);
+    }
+
+    #[test]
+    fn test_report() {
+        let report = tournament(100, 4).run().unwrap();
+        let [random, heuristic] = &report.entrants[..] else {
+            panic!("two entrants");
+        };
+        assert_eq!(random.games + heuristic.games, 300 * 3);
+        assert_eq!(random.wins + heuristic.wins, 300);
+        // In a two entrant field the one winning more of their seats is rated higher.
+        assert_eq!(
+            heuristic.win_rate.value > random.win_rate.value,
+            heuristic.rating.value > random.rating.value
+        );
+        for entrant in &report.entrants {
+            assert!(entrant.rating.low <= entrant.rating.value);
+            assert!(entrant.rating.value <= entrant.rating.high);
+            assert!(entrant.win_rate.low <= entrant.win_rate.value);
+            assert!(entrant.win_rate.value <= entrant.win_rate.high);
+        }
+        assert_eq!(
+            report.seats.iter().map(|seat| seat.wins).sum::<usize>(),
+            300
+        );
+    }
+
+    #[test]
+    fn test_luce_ratings() {
+        // Entrants 0 and 2 win twice each, and 1 never does.
+        let game = |entrants: Vec<usize>, winner| GameRecord {
+            round: 0,
+            seed: 0,
+            entrants,
+            winner: Some(winner),
+            error: None,
+        };
+        let games = [
+            game(vec![0, 1, 2], 0),
+            game(vec![2, 1, 0], 0),
+            game(vec![1, 2, 0], 1),
+            game(vec![0, 2, 1], 0),
+        ];
+        let ratings = luce_ratings(3, &games.iter().collect::<Vec<_>>());
+        assert!((ratings[0] - ratings[2]).abs() < 1e-6);
+        assert!(ratings[1] < ratings[0]);
+        assert!((ratings.iter().sum::<f64>() / 3.0 - 1500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_wilson() {
+        let estimate = wilson(50, 100);
+        assert_eq!(estimate.value, 0.5);
+        assert!((estimate.low - 0.404).abs() < 0.001);
+        assert!((estimate.high - 0.596).abs() < 0.001);
+        assert_eq!(wilson(0, 10).low, 0.0);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut tournament = tournament(1, 1);
+        tournament.config.players_per_game = 7;
+        assert!(matches!(
+            tournament.schedule(),
+            Err(TournamentError::PlayerCount(7))
+        ));
+        assert!(matches!(
+            Tournament::new(TournamentConfig::default()).schedule(),
+            Err(TournamentError::NoEntrants)
+        ));
+    }
+}
@@ -0,0 +1,449 @@
+use crate::events::{GameEvent, SeatedObserver};
+use crate::terminal::PLAYER_COLORS;
+use crate::{Decision, Game, PlayerReference, SpaceCard};
+use miette::IntoDiagnostic;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Pauses between bot decisions to cycle through with `+` and `-`.
+const DELAYS: [Duration; 6] = [
+    Duration::from_millis(2000),
+    Duration::from_millis(1000),
+    Duration::from_millis(500),
+    Duration::from_millis(250),
+    Duration::from_millis(100),
+    Duration::from_millis(0),
+];
+
+/// How the TUI runs the game.
+#[derive(Debug, Clone, Default)]
+pub struct TuiOptions {
+    /// The seat played from the keyboard. It must not have a controller.
+    pub human: Option<PlayerReference>,
+
+    /// Start paused, stepping with `n`.
+    pub paused: bool,
+
+    /// Show every hand, which is only allowed when nobody is playing.
+    pub show_hands: bool,
+}
+
+/// Watch or play a game full screen until it is over and the viewer quits.
+///
+/// Bots are stepped one decision at a time, so the game can be paused, stepped and sped up.
+/// Whenever the human seat has to decide, the choices are listed as a menu.
+pub fn run_tui(game: &mut Game, options: TuiOptions) -> miette::Result<()> {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let recorded = log.clone();
+    let record = move |_: &Game, event: &GameEvent| {
+        recorded.borrow_mut().push(event.clone());
+    };
+    // A person playing only gets to read about what their seat saw.
+    match options.human {
+        Some(human) => game.add_observer(Box::new(SeatedObserver::new(human, record))),
+        None => game.add_observer(Box::new(record)),
+    }
+
+    let mut terminal = ratatui::init();
+    let result = Tui::new(options, log).run(&mut terminal, game);
+    ratatui::restore();
+    result
+}
+
+struct Tui {
+    human: Option<PlayerReference>,
+    paused: bool,
+    delay: usize,
+    show_hands: bool,
+    log: Rc<RefCell<Vec<GameEvent>>>,
+    menu: ListState,
+    message: Option<String>,
+    quit: bool,
+}
+
+impl Tui {
+    fn new(options: TuiOptions, log: Rc<RefCell<Vec<GameEvent>>>) -> Self {
+        Self {
+            human: options.human,
+            paused: options.paused,
+            delay: 2,
+            show_hands: options.show_hands && options.human.is_none(),
+            log,
+            menu: ListState::default(),
+            message: None,
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, game: &mut Game) -> miette::Result<()> {
+        if game.pending_decision().is_none() && game.winner().is_none() {
+            game.start()?;
+        }
+
+        while !self.quit {
+            let choices = self.human_choices(game);
+            terminal
+                .draw(|frame| self.render(frame, game, &choices))
+                .into_diagnostic()?;
+
+            if !choices.is_empty() {
+                if let Some(key) = read_key()? {
+                    self.menu_key(key, game, &choices)?;
+                }
+                continue;
+            }
+
+            // Bots move on their own unless something is waiting to be read.
+            let waiting = game.winner().is_some() || self.paused;
+            if !waiting && !event::poll(DELAYS[self.delay]).into_diagnostic()? {
+                game.step()?;
+            } else if let Some(key) = read_key()? {
+                self.key(key, game)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// What the human can pick from, if the game is waiting on them.
+    fn human_choices(&self, game: &Game) -> Vec<Decision> {
+        match (game.pending_decision(), self.human) {
+            (Some(pending), Some(human)) if pending.player() == human => {
+                game.state().legal_decisions()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn key(&mut self, key: KeyCode, game: &mut Game) -> miette::Result<()> {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('n') if game.pending_decision().is_some() => {
+                game.step()?;
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.delay = (self.delay + 1).min(DELAYS.len() - 1);
+            }
+            KeyCode::Char('-') => self.delay = self.delay.saturating_sub(1),
+            KeyCode::Char('h') if self.human.is_none() => self.show_hands = !self.show_hands,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn menu_key(
+        &mut self,
+        key: KeyCode,
+        game: &mut Game,
+        choices: &[Decision],
+    ) -> miette::Result<()> {
+        let selected = self.menu.selected().unwrap_or(0).min(choices.len() - 1);
+        let pick = match key {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                None
+            }
+            KeyCode::Up => {
+                self.menu.select(Some(selected.saturating_sub(1)));
+                None
+            }
+            KeyCode::Down => {
+                self.menu
+                    .select(Some((selected + 1).min(choices.len() - 1)));
+                None
+            }
+            KeyCode::Enter => Some(selected),
+            KeyCode::Char(c) => match c.to_digit(10) {
+                Some(digit) if digit >= 1 && (digit as usize) <= choices.len() => {
+                    Some(digit as usize - 1)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(idx) = pick {
+            match game.submit(choices[idx].clone()) {
+                Ok(_) => {
+                    self.message = None;
+                    self.menu.select(Some(0));
+                }
+                Err(err) => self.message = Some(err.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&self, frame: &mut Frame, game: &Game, choices: &[Decision]) {
+        let bottom_height = if choices.is_empty() {
+            3
+        } else {
+            choices.len() as u16 + 3
+        };
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Min(8),
+                Constraint::Length(bottom_height),
+            ])
+            .split(frame.area());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[1]);
+
+        self.render_header(frame, rows[0], game);
+        self.render_board(frame, columns[0], game);
+        self.render_log(frame, columns[1]);
+        if choices.is_empty() {
+            self.render_controls(frame, rows[2]);
+        } else {
+            self.render_menu(frame, rows[2], choices);
+        }
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect, game: &Game) {
+        let state = game.state();
+        let status = match (game.winner(), game.pending_decision()) {
+            (Some(winner), _) => format!("Player {} won!", winner.0),
+            (None, Some(pending)) => pending.to_string(),
+            (None, None) => String::new(),
+        };
+        let speed = if self.paused {
+            "paused".to_string()
+        } else {
+            format!("{}ms per move", DELAYS[self.delay].as_millis())
+        };
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    format!("Player {}'s turn", state.whose_turn().0),
+                    Style::default().fg(player_color(&state.whose_turn())),
+                ),
+                Span::raw(format!(
+                    "  |  Phase: {}  |  Ship: {} spaces  |  {}",
+                    state.phase(),
+                    state.ship_distance(),
+                    speed
+                )),
+            ]),
+            Line::from(status),
+        ];
+        let header = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Selfish: Space Edition "),
+        );
+        frame.render_widget(header, area);
+    }
+
+    fn render_board(&self, frame: &mut Frame, area: Rect, game: &Game) {
+        let state = game.state();
+        let mut lines = Vec::new();
+        for idx in 0..state.player_count() {
+            let player_reference = PlayerReference(idx);
+            let player = match state.player(&player_reference) {
+                Ok(player) => player,
+                Err(_) => continue,
+            };
+
+            let marker = if state.whose_turn() == player_reference {
+                "▶ "
+            } else {
+                "  "
+            };
+            let mut name = format!("Player {}", idx);
+            if Some(player_reference) == self.human {
+                name.push_str(" (you)");
+            }
+            let status = if player.alive {
+                format!("{} cards", player.hand.len())
+            } else {
+                "dead".to_string()
+            };
+            let mut style = Style::default().fg(player_color(&player_reference));
+            if !player.alive {
+                style = style.add_modifier(Modifier::CROSSED_OUT);
+            }
+            lines.push(Line::from(vec![
+                Span::raw(marker),
+                Span::styled(name, style.add_modifier(Modifier::BOLD)),
+                Span::raw(format!("  {}", status)),
+            ]));
+
+            let mut track = vec![Span::raw("    ")];
+            for slot in 0..state.ship_distance().max(player.space.len()) {
+                match player.space.get(slot) {
+                    Some(card) => {
+                        let (label, color) = space_label(card);
+                        track.push(Span::styled(
+                            format!("[{}]", label),
+                            Style::default().fg(color),
+                        ));
+                    }
+                    None => track.push(Span::styled("[   ]", Style::default().fg(Color::DarkGray))),
+                }
+            }
+            track.push(Span::styled(" SHIP", Style::default().fg(Color::Cyan)));
+            lines.push(Line::from(track));
+
+            if self.show_hands || Some(player_reference) == self.human {
+                lines.push(Line::from(format!("    Hand: {:?}", player.hand)));
+            }
+            lines.push(Line::from(""));
+        }
+
+        let discard_pile = state.discard_pile();
+        let top = match discard_pile.last() {
+            None => "empty".to_string(),
+            Some(card) => format!("{:?} on top", card),
+        };
+        lines.push(Line::from(format!(
+            "Discard pile: {} cards, {}",
+            discard_pile.len(),
+            top
+        )));
+
+        let board =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Board "));
+        frame.render_widget(board, area);
+    }
+
+    fn render_log(&self, frame: &mut Frame, area: Rect) {
+        let log = self.log.borrow();
+        // Only the newest events fit, so skip the rest.
+        let height = area.height.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = log
+            .iter()
+            .skip(log.len().saturating_sub(height))
+            .map(|event| {
+                let style = match event.player() {
+                    None => Style::default(),
+                    Some(player) => Style::default().fg(player_color(&player)),
+                };
+                ListItem::new(Line::styled(event.to_string(), style))
+            })
+            .collect();
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" Events "));
+        frame.render_widget(list, area);
+    }
+
+    fn render_controls(&self, frame: &mut Frame, area: Rect) {
+        let mut controls = "space: pause  n: step  +/-: speed  q: quit".to_string();
+        if self.human.is_none() {
+            controls.push_str("  h: show hands");
+        }
+        let help = Paragraph::new(controls).block(Block::default().borders(Borders::ALL));
+        frame.render_widget(help, area);
+    }
+
+    fn render_menu(&self, frame: &mut Frame, area: Rect, choices: &[Decision]) {
+        let items: Vec<ListItem> = choices
+            .iter()
+            .enumerate()
+            .map(|(idx, decision)| ListItem::new(format!("{}) {}", idx + 1, decision)))
+            .collect();
+        let title = match &self.message {
+            None => " Your move (arrows and enter, or a number) ".to_string(),
+            Some(message) => format!(" {} ", message),
+        };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        let mut menu = self.menu.clone();
+        if menu.selected().is_none() {
+            menu.select(Some(0));
+        }
+        frame.render_stateful_widget(list, area, &mut menu);
+    }
+}
+
+/// Wait for the next key press, ignoring releases and other events.
+fn read_key() -> miette::Result<Option<KeyCode>> {
+    match event::read().into_diagnostic()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => Ok(Some(key.code)),
+        _ => Ok(None),
+    }
+}
+
+fn player_color(player_reference: &PlayerReference) -> Color {
+    let (r, g, b) = PLAYER_COLORS[player_reference.0 % PLAYER_COLORS.len()];
+    Color::Rgb(r, g, b)
+}
+
+fn space_label(card: &SpaceCard) -> (&'static str, Color) {
+    match card {
+        SpaceCard::BlankSpace => ("   ", Color::Gray),
+        SpaceCard::UsefulJunk => ("JNK", Color::Green),
+        SpaceCard::MysteriousNebula => ("NEB", Color::Green),
+        SpaceCard::Hyperspace => ("HYP", Color::LightBlue),
+        SpaceCard::Meteoroid => ("MET", Color::Red),
+        SpaceCard::CosmicRadiation => ("COS", Color::Red),
+        SpaceCard::AsteroidField => ("AST", Color::Red),
+        SpaceCard::GravitationalAnomaly => ("GRV", Color::Magenta),
+        SpaceCard::WormHole => ("WRM", Color::Magenta),
+        SpaceCard::SolarFlare => ("SOL", Color::Yellow),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn draw(tui: &Tui, game: &Game) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        let choices = tui.human_choices(game);
+        terminal
+            .draw(|frame| tui.render(frame, game, &choices))
+            .unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn test_spectator_screen() {
+        let mut game = Game::headless(Some(1), 3);
+        let tui = Tui::new(TuiOptions::default(), Rc::new(RefCell::new(Vec::new())));
+        game.start().unwrap();
+        let screen = draw(&tui, &game);
+        assert!(screen.contains("Player 2"));
+        assert!(screen.contains("Phase: Actions"));
+        assert!(screen.contains("Discard pile: 0 cards"));
+        assert!(screen.contains("space: pause"));
+        assert!(!screen.contains("Hand:"));
+    }
+
+    #[test]
+    fn test_human_menu() {
+        let mut game = Game::headless(Some(1), 3);
+        let options = TuiOptions {
+            human: Some(PlayerReference(0)),
+            show_hands: true,
+            ..TuiOptions::default()
+        };
+        let tui = Tui::new(options, Rc::new(RefCell::new(Vec::new())));
+        game.start().unwrap();
+        let screen = draw(&tui, &game);
+        assert!(screen.contains("Player 0 (you)"));
+        assert!(screen.contains("1) Nothing, move on to breathing or travelling"));
+        // Only their own hand, even though show_hands was asked for.
+        assert_eq!(screen.matches("Hand:").count(), 1);
+    }
+}
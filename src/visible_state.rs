@@ -1,49 +1,643 @@
-use crate::{Game, GameCard, PlayerReference, SpaceCard};
+use crate::actions::BreatheOrTravel;
+use crate::errors::SelfishError;
+use crate::{
+    Action, Game, GameCard, GameEvent, GameState, Phase, PlayerReference, RuleSet, SpaceCard,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-/// Information that a fair player can observe about the game.
+/// Information that a fair player can observe about the game, from one seat.
 ///
-/// * Whose turn it is
-/// * The number of cards in each player's hand
+/// * Whose turn it is, and how far through it they are
+/// * The viewer's own hand
+/// * The number of cards in each player's hand, and any of them the viewer knows about
 /// * The space grid.
+/// * How far away the ship is.
+/// * The rules of the game, including what the decks are made of.
+/// * Everything the viewer has seen happen, the discard piles and what is left in the decks.
+#[derive(Debug, Clone, PartialEq)]
 pub struct VisibleState {
+    /// The player this is the view of, whose hand `my_hand` is. Not necessarily whose turn it
+    /// is, e.g. when deciding whether to defend.
+    pub viewer: PlayerReference,
     pub whose_turn: PlayerReference,
+
+    /// Where the current player is in their turn, e.g. whether a space card was reached by
+    /// travelling or by a rocket booster during their actions.
+    pub phase: Phase,
+
+    pub ship_distance: usize,
+    pub rules: Arc<RuleSet>,
     pub my_hand: Vec<GameCard>,
     pub players: Vec<VisiblePlayer>,
+
+    /// Every event the viewer saw, oldest first. Cards that other players drew, or that changed
+    /// hands in secret, only show up for the players involved.
+    pub history: Vec<GameEvent>,
+
+    /// Face up, with the latest discard last.
+    pub discard_pile: Vec<GameCard>,
+
+    /// Cards left to draw.
+    pub deck_size: usize,
+
+    /// How many of each space card are left to draw. Every space card is face up once drawn, so
+    /// anybody can work this out.
+    pub space_deck: BTreeMap<SpaceCard, usize>,
+
+    /// Space cards knocked out of rows, shuffled back in when the space deck runs out.
+    pub space_discard_pile: Vec<SpaceCard>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VisiblePlayer {
     pub alive: bool,
     pub hand_size: usize,
     pub space: Vec<SpaceCard>,
+
+    /// Cards the viewer knows are in the player's hand, from their `BeliefState`. Empty for the
+    /// viewer, whose hand is `my_hand`.
+    pub known_cards: Vec<GameCard>,
+}
+
+impl VisiblePlayer {
+    pub fn in_solar_flare(&self) -> bool {
+        self.space.last() == Some(&SpaceCard::SolarFlare)
+    }
 }
 
 impl VisibleState {
-    pub fn try_from_game(game: &Game) -> miette::Result<Self> {
-        let whose_turn = game.whose_turn_reference;
-        let players = (0..game.player_count())
+    pub fn try_from_game(game: &Game, viewer: &PlayerReference) -> miette::Result<Self> {
+        Self::try_from_state(game.state(), viewer)
+    }
+
+    /// What the viewer can see of the state.
+    pub fn try_from_state(state: &GameState, viewer: &PlayerReference) -> miette::Result<Self> {
+        let mut visible_state = Self::for_rules(state, viewer)?;
+        let belief = state.belief(viewer)?;
+        for (idx, player) in visible_state.players.iter_mut().enumerate() {
+            player.known_cards = belief.known_cards(&PlayerReference(idx)).to_vec();
+        }
+
+        let (space_cards, space_discard_pile) = state.space_deck();
+        for card in space_cards {
+            *visible_state.space_deck.entry(card.clone()).or_default() += 1;
+        }
+
+        visible_state.history = state.history(viewer);
+        visible_state.discard_pile = state.discard_pile().to_vec();
+        visible_state.deck_size = state.deck_size();
+        visible_state.space_discard_pile = space_discard_pile.to_vec();
+        Ok(visible_state)
+    }
+
+    /// Only the table, which is all the rules need to check the viewer's decisions. Leaves out
+    /// the history, discard piles, decks and known cards, which grow with the game and would make
+    /// every check slower as it goes on.
+    pub(crate) fn for_rules(state: &GameState, viewer: &PlayerReference) -> miette::Result<Self> {
+        let players = (0..state.player_count())
             .map(|player_reference| {
-                let player = game.player(&PlayerReference(player_reference))?;
+                let player = state.player(&PlayerReference(player_reference))?;
                 Ok(VisiblePlayer {
                     hand_size: player.hand.len(),
                     space: player.space.clone(),
                     alive: player.alive,
+                    known_cards: Vec::new(),
                 })
             })
             .collect::<miette::Result<Vec<VisiblePlayer>>>()?;
-        let my_hand = game.player(&whose_turn)?.hand.clone();
 
         Ok(VisibleState {
-            whose_turn,
-            my_hand,
+            viewer: *viewer,
+            whose_turn: state.whose_turn(),
+            phase: state.phase(),
+            ship_distance: state.ship_distance(),
+            rules: state.shared_rules(),
+            my_hand: state.player(viewer)?.hand.clone(),
             players,
+            history: Vec::new(),
+            discard_pile: Vec::new(),
+            deck_size: 0,
+            space_deck: BTreeMap::new(),
+            space_discard_pile: Vec::new(),
         })
     }
 
+    /// Whether the viewer may play the action right now, assuming it is their action phase.
+    ///
+    /// This is the same check the engine makes before resolving an action.
+    pub fn check_action(&self, action: &Action) -> Result<(), SelfishError> {
+        let me = self.player(&self.viewer)?;
+        if me.in_solar_flare() && !self.rules.house_rules.actions_in_solar_flare {
+            return Err(SelfishError::InSolarFlare);
+        }
+
+        let card = action.card();
+        if !self.my_hand.contains(&card) {
+            return Err(SelfishError::PlayerDoesNotHaveThisCard(card));
+        }
+
+        if let Some(target) = action.attacking() {
+            if target == self.viewer {
+                return Err(SelfishError::CantAttackYourself);
+            }
+
+            let other_player = self.player(&target)?;
+            if !other_player.alive {
+                return Err(SelfishError::PlayerIsDead(target));
+            }
+
+            let rules = action.rules();
+            if let Some(steal) = rules.steal {
+                if steal.count > other_player.hand_size {
+                    return Err(SelfishError::PlayerDoesNotHaveEnoughCards(
+                        target,
+                        steal.count,
+                    ));
+                }
+            }
+            if rules.needs_space && other_player.space.is_empty() {
+                return Err(SelfishError::PlayerHasNoSpace(target));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every action the viewer could play in their action phase, with every valid target.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        let mut cards = self.my_hand.clone();
+        dedup_in_order(&mut cards);
+
+        let mut actions = Vec::new();
+        for card in cards {
+            for idx in 0..self.players.len() {
+                let action = match Action::from_card(card, PlayerReference(idx)) {
+                    None => break,
+                    Some(action) => action,
+                };
+                let untargeted = action.attacking().is_none();
+                if self.check_action(&action).is_ok() {
+                    actions.push(action);
+                }
+                if untargeted {
+                    break;
+                }
+            }
+        }
+        actions
+    }
+
+    /// The choices the player has at the end of their turn.
+    ///
+    /// Empty means they have no oxygen left and will die. With a single option it is played
+    /// without asking the controller.
+    pub fn legal_breathe_or_travel(&self) -> Vec<BreatheOrTravel> {
+        let mut options = Vec::new();
+        if self.my_hand.contains(&GameCard::O1) {
+            options.push(BreatheOrTravel::Breathe);
+        }
+        if self.my_hand.contains(&GameCard::O2) {
+            options.push(BreatheOrTravel::Travel);
+        }
+        options
+    }
+
+    /// Players that can be swapped with after landing on a wormhole.
+    pub fn legal_swap_targets(&self) -> Vec<PlayerReference> {
+        (0..self.players.len())
+            .map(PlayerReference)
+            .filter(|target| self.check_swap_target(target).is_ok())
+            .collect()
+    }
+
+    pub fn check_swap_target(&self, target: &PlayerReference) -> Result<(), SelfishError> {
+        let other_player = self.player(target)?;
+        if *target == self.viewer || !other_player.alive {
+            return Err(SelfishError::InvalidSwapTarget(*target));
+        }
+        Ok(())
+    }
+
+    /// Every distinct set of cards from the hand that could be thrown away, ignoring order.
+    pub fn legal_discards(&self, card_count: usize) -> Vec<Vec<GameCard>> {
+        let mut cards = self.my_hand.clone();
+        dedup_in_order(&mut cards);
+        let counts: Vec<(GameCard, usize)> = cards
+            .into_iter()
+            .map(|card| (card, self.my_hand.iter().filter(|c| **c == card).count()))
+            .collect();
+
+        let mut discards = Vec::new();
+        collect_discards(&counts, card_count, &mut Vec::new(), &mut discards);
+        discards
+    }
+
+    pub fn check_discard(&self, cards: &[GameCard], card_count: usize) -> Result<(), SelfishError> {
+        if cards.len() != card_count {
+            return Err(SelfishError::InvalidDiscardCount {
+                expected: card_count,
+                actual: cards.len(),
+            });
+        }
+
+        let mut hand = self.my_hand.clone();
+        for card in cards {
+            let idx = hand
+                .iter()
+                .position(|c| c == card)
+                .ok_or(SelfishError::PlayerDoesNotHaveThisCard(*card))?;
+            hand.remove(idx);
+        }
+        Ok(())
+    }
+
+    /// How many of each card could be in the deck or hidden in other players' hands: everything
+    /// not in the viewer's hand, the discard pile or known to be held by somebody.
+    pub fn unseen_cards(&self) -> BTreeMap<GameCard, usize> {
+        let mut unseen = self.rules.decks.game_cards.clone();
+        let seen = self
+            .players
+            .iter()
+            .flat_map(|player| &player.known_cards)
+            .chain(&self.my_hand)
+            .chain(&self.discard_pile);
+        for card in seen {
+            if let Some(count) = unseen.get_mut(card) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        unseen.retain(|_, count| *count > 0);
+        unseen
+    }
+
+    fn player(&self, player_reference: &PlayerReference) -> Result<&VisiblePlayer, SelfishError> {
+        self.players
+            .get(player_reference.0)
+            .ok_or(SelfishError::PlayerDoesNotExist(*player_reference))
+    }
+
     pub fn invalid() -> Self {
         VisibleState {
+            viewer: PlayerReference(42),
             whose_turn: PlayerReference(42),
+            phase: Phase::Pickup,
+            ship_distance: 0,
+            rules: Arc::default(),
             my_hand: vec![],
             players: Vec::new(),
+            history: Vec::new(),
+            discard_pile: Vec::new(),
+            deck_size: 0,
+            space_deck: BTreeMap::new(),
+            space_discard_pile: Vec::new(),
+        }
+    }
+}
+
+/// A living player the viewer knows nothing about the hand of, for building views in tests.
+#[cfg(test)]
+pub(crate) fn visible_player(hand_size: usize, space: Vec<SpaceCard>) -> VisiblePlayer {
+    VisiblePlayer {
+        alive: true,
+        hand_size,
+        space,
+        known_cards: vec![],
+    }
+}
+
+fn dedup_in_order(cards: &mut Vec<GameCard>) {
+    let mut seen = Vec::new();
+    cards.retain(|card| {
+        if seen.contains(card) {
+            false
+        } else {
+            seen.push(*card);
+            true
+        }
+    });
+}
+
+/// Picks `remaining` more cards out of `counts`, only ever moving forward through the card types
+/// so that each combination comes out once.
+fn collect_discards(
+    counts: &[(GameCard, usize)],
+    remaining: usize,
+    current: &mut Vec<GameCard>,
+    discards: &mut Vec<Vec<GameCard>>,
+) {
+    if remaining == 0 {
+        discards.push(current.clone());
+        return;
+    }
+
+    let ((card, available), rest) = match counts.split_first() {
+        None => return,
+        Some(split) => split,
+    };
+
+    for taken in (0..=(*available).min(remaining)).rev() {
+        let len = current.len();
+        current.extend(std::iter::repeat_n(*card, taken));
+        collect_discards(rest, remaining - taken, current, discards);
+        current.truncate(len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Decision, HeuristicPlayerController, PendingDecision, PlayerController,
+        RandomPlayerController, Scenario,
+    };
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    fn state(my_hand: Vec<GameCard>) -> VisibleState {
+        VisibleState {
+            viewer: PlayerReference(0),
+            whose_turn: PlayerReference(0),
+            ship_distance: 6,
+            rules: Arc::default(),
+            players: vec![
+                visible_player(my_hand.len(), vec![]),
+                visible_player(1, vec![SpaceCard::BlankSpace]),
+                visible_player(5, vec![]),
+                VisiblePlayer {
+                    alive: false,
+                    ..visible_player(0, vec![SpaceCard::BlankSpace])
+                },
+            ],
+            my_hand,
+            ..VisibleState::invalid()
+        }
+    }
+
+    #[test]
+    fn test_legal_actions() {
+        let state = state(vec![
+            GameCard::O1,
+            GameCard::OxygenSiphon,
+            GameCard::Tether,
+            GameCard::Tether,
+            GameCard::RocketBooster,
+        ]);
+        let p = PlayerReference;
+        assert_eq!(
+            state.legal_actions(),
+            vec![
+                Action::OxygenSiphon { target: p(1) },
+                Action::OxygenSiphon { target: p(2) },
+                Action::Tether { target: p(1) },
+                Action::RocketBooster,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_legal_actions_in_solar_flare() {
+        let mut state = state(vec![GameCard::RocketBooster]);
+        state.players[0].space.push(SpaceCard::SolarFlare);
+        assert!(state.legal_actions().is_empty());
+        assert!(matches!(
+            state.check_action(&Action::RocketBooster),
+            Err(SelfishError::InSolarFlare)
+        ));
+    }
+
+    #[test]
+    fn test_legal_swap_targets() {
+        let state = state(vec![]);
+        assert_eq!(
+            state.legal_swap_targets(),
+            vec![PlayerReference(1), PlayerReference(2)]
+        );
+    }
+
+    #[test]
+    fn test_legal_discards() {
+        let state = state(vec![
+            GameCard::O1,
+            GameCard::O1,
+            GameCard::O2,
+            GameCard::Shield,
+        ]);
+        let discards = state.legal_discards(2);
+        assert_eq!(discards.len(), 4);
+        for discard in &discards {
+            assert!(state.check_discard(discard, 2).is_ok());
+        }
+        assert!(state
+            .check_discard(&[GameCard::O2, GameCard::O2], 2)
+            .is_err());
+        assert!(state.check_discard(&[GameCard::O1], 2).is_err());
+    }
+
+    #[test]
+    fn test_legal_breathe_or_travel() {
+        assert_eq!(
+            state(vec![GameCard::O2, GameCard::O1]).legal_breathe_or_travel(),
+            vec![BreatheOrTravel::Breathe, BreatheOrTravel::Travel]
+        );
+        assert!(state(vec![GameCard::Shield])
+            .legal_breathe_or_travel()
+            .is_empty());
+    }
+
+    /// Passes everything through to the inner controller, keeping every view it was given along
+    /// with the decision that followed it. Panics if a decision comes without a fresh view.
+    struct Spy {
+        inner: Box<dyn PlayerController>,
+        fresh: Option<VisibleState>,
+        seen: Rc<RefCell<Vec<(VisibleState, PendingDecision)>>>,
+    }
+
+    impl PlayerController for Spy {
+        fn update_state(&mut self, visible_state: VisibleState) {
+            self.fresh = Some(visible_state.clone());
+            self.inner.update_state(visible_state);
+        }
+
+        fn play_action(&mut self) -> Option<Action> {
+            self.inner.play_action()
+        }
+
+        fn breathe_or_travel(&mut self) -> BreatheOrTravel {
+            self.inner.breathe_or_travel()
+        }
+
+        fn defend(&mut self, action: &Action) -> bool {
+            self.inner.defend(action)
+        }
+
+        fn forced_discard(&mut self, card_count: usize) -> Vec<GameCard> {
+            self.inner.forced_discard(card_count)
+        }
+
+        fn choose_player_to_swap_with(&mut self) -> PlayerReference {
+            self.inner.choose_player_to_swap_with()
+        }
+
+        fn choose_card_to_take(&mut self, options: HashSet<GameCard>) -> GameCard {
+            self.inner.choose_card_to_take(options)
+        }
+
+        fn decide(&mut self, pending: &PendingDecision) -> Decision {
+            let view = self
+                .fresh
+                .take()
+                .expect("asked to decide without a fresh view");
+            self.seen.borrow_mut().push((view, pending.clone()));
+            self.inner.decide(pending)
+        }
+    }
+
+    #[test]
+    fn test_every_decision_gets_the_deciders_view() {
+        let mut off_turn = 0;
+        for seed in 0..30 {
+            let seen = Rc::new(RefCell::new(Vec::new()));
+            let controllers = (0..4)
+                .map(|seat| {
+                    let inner: Box<dyn PlayerController> = if seat % 2 == 0 {
+                        Box::new(RandomPlayerController::with_seed(seed * 10 + seat))
+                    } else {
+                        Box::new(HeuristicPlayerController::default())
+                    };
+                    Box::new(Spy {
+                        inner,
+                        fresh: None,
+                        seen: seen.clone(),
+                    }) as Box<dyn PlayerController>
+                })
+                .collect();
+            let mut game = Game::new(Some(seed), controllers);
+
+            let mut pending = game.start().unwrap();
+            while pending.is_some() {
+                let before = game.state().clone();
+                pending = game.step().unwrap();
+
+                for (view, decision) in seen.borrow_mut().drain(..) {
+                    let seat = decision.player();
+                    assert_eq!(view.viewer, seat);
+                    assert_eq!(view.whose_turn, before.whose_turn());
+                    assert_eq!(view.my_hand, before.player(&seat).unwrap().hand);
+                    if seat != view.whose_turn {
+                        off_turn += 1;
+                    }
+                }
+            }
+        }
+        // Shields and hack suits ask players outside their own turn.
+        assert!(off_turn > 0);
+    }
+
+    #[test]
+    fn test_views_dont_depend_on_hidden_cards() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for seed in 0..20 {
+            let mut game = Game::headless(Some(seed), 4);
+            let mut pending = game.start().unwrap();
+            while pending.is_some() {
+                let state = game.state();
+                for viewer in (0..4).map(PlayerReference) {
+                    let view = state.visible_state(&viewer).unwrap();
+                    // Any other deal of the cards the viewer can't see looks the same to them.
+                    let mut other = state.clone();
+                    other.determinize(&viewer, &mut rng);
+                    assert_eq!(other.visible_state(&viewer).unwrap(), view);
+
+                    // The engine checks decisions against the lighter view, to the same effect.
+                    let rules_view = VisibleState::for_rules(state, &viewer).unwrap();
+                    assert_eq!(rules_view.legal_actions(), view.legal_actions());
+                    assert_eq!(
+                        rules_view.legal_breathe_or_travel(),
+                        view.legal_breathe_or_travel()
+                    );
+                }
+
+                let decisions = state.legal_decisions();
+                let decision = decisions[rng.gen_range(0..decisions.len())].clone();
+                pending = game.submit(decision).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_history_hides_secret_cards() {
+        let mut state = Scenario::new(3)
+            .hand(1, [GameCard::LaserBlast])
+            .deck([GameCard::TractorBeam])
+            .build()
+            .unwrap();
+        state.start().unwrap();
+        state
+            .apply(Decision::PlayAction(Some(Action::TractorBeam {
+                target: PlayerReference(1),
+            })))
+            .unwrap();
+
+        let view = |viewer| state.visible_state(&PlayerReference(viewer)).unwrap();
+        let saw = |view: &VisibleState, f: fn(&GameEvent) -> bool| view.history.iter().any(f);
+        let drew = |event: &GameEvent| matches!(event, GameEvent::CardDrawn { .. });
+        let stole = |event: &GameEvent| matches!(event, GameEvent::CardStolen { .. });
+        assert!(saw(&view(0), drew) && saw(&view(0), stole));
+        assert!(!saw(&view(1), drew) && saw(&view(1), stole));
+        assert!(!saw(&view(2), drew) && !saw(&view(2), stole));
+        assert!(saw(&view(2), |event| matches!(
+            event,
+            GameEvent::ActionPlayed { .. }
+        )));
+
+        assert_eq!(view(1).players[0].known_cards, vec![GameCard::LaserBlast]);
+        assert_eq!(view(2).players[0].known_cards, vec![]);
+        assert_eq!(view(0).discard_pile, vec![GameCard::TractorBeam]);
+    }
+
+    #[test]
+    fn test_known_cards_are_held() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut known = 0;
+        for seed in 0..20 {
+            let mut game = Game::headless(Some(seed), 4);
+            let mut pending = game.start().unwrap();
+            while pending.is_some() {
+                let state = game.state();
+                for viewer in (0..4).map(PlayerReference) {
+                    let view = state.visible_state(&viewer).unwrap();
+                    assert_eq!(view.discard_pile, state.discard_pile());
+                    assert_eq!(view.deck_size, state.deck_size());
+                    assert_eq!(
+                        view.space_deck.values().sum::<usize>(),
+                        state.space_deck().0.len()
+                    );
+                    for (idx, player) in view.players.iter().enumerate() {
+                        let mut hand = state.player(&PlayerReference(idx)).unwrap().hand.clone();
+                        for card in &player.known_cards {
+                            let pos = hand.iter().position(|c| c == card);
+                            assert!(
+                                pos.is_some(),
+                                "{:?} thinks {} holds {:?}",
+                                viewer,
+                                idx,
+                                card
+                            );
+                            hand.remove(pos.unwrap());
+                            known += 1;
+                        }
+                    }
+                }
+
+                let decisions = state.legal_decisions();
+                let decision = decisions[rng.gen_range(0..decisions.len())].clone();
+                pending = game.submit(decision).unwrap();
+            }
         }
+        assert!(known > 0);
     }
 }